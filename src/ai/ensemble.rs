@@ -0,0 +1,282 @@
+use super::models::{AIProvider, AnalysisResult};
+use super::{AIError, AIService};
+use crate::config::AIConfig;
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// 多提供商共识投票服务
+///
+/// 将同一条消息并发分发给所有配置的子提供商，在共享截止时间内收集
+/// 各自的 `AnalysisResult`，再按置信度加权投票合并为一个结果。这样
+/// 单个模型的幻觉（例如编造一个合约地址）不会单独左右最终结论。
+pub struct EnsembleService {
+    services: Vec<Box<dyn AIService>>,
+    quorum: usize,
+    timeout: Duration,
+}
+
+impl EnsembleService {
+    /// 创建共识投票服务
+    pub fn new(ai_config: &AIConfig) -> Result<Self, AIError> {
+        let ensemble_config = ai_config.ensemble.as_ref()
+            .ok_or_else(|| AIError::ConfigError("Ensemble 配置未找到".to_string()))?;
+
+        let mut services: Vec<Box<dyn AIService>> = Vec::new();
+        for provider in &ensemble_config.providers {
+            let service: Box<dyn AIService> = match provider.to_lowercase().as_str() {
+                "ollama" | "local" => Box::new(super::local::OllamaService::new(ai_config)?),
+                "kimi" => Box::new(super::kimi::KimiService::new(ai_config)?),
+                "openai" => Box::new(super::openai::OpenAIService::new(ai_config)?),
+                other => {
+                    return Err(AIError::UnsupportedProvider(other.to_string()));
+                }
+            };
+            services.push(service);
+        }
+
+        if ensemble_config.quorum == 0 || ensemble_config.quorum > services.len() {
+            return Err(AIError::ConfigError(format!(
+                "ai.ensemble.quorum ({}) 必须介于 1 到子提供商数量 ({}) 之间",
+                ensemble_config.quorum,
+                services.len()
+            )));
+        }
+
+        Ok(Self {
+            services,
+            quorum: ensemble_config.quorum,
+            timeout: Duration::from_secs(ai_config.timeout_seconds),
+        })
+    }
+
+    /// 合并多个子提供商结果为一个共识结果
+    fn merge(&self, results: Vec<AnalysisResult>) -> AnalysisResult {
+        let survivors: Vec<AnalysisResult> = results.into_iter().filter(|r| r.is_relevant).collect();
+
+        if survivors.is_empty() {
+            return AnalysisResult::empty();
+        }
+
+        // 按归一化后的 recommendation 分桶，累加置信度作为该桶的票重
+        let mut buckets: HashMap<String, Vec<&AnalysisResult>> = HashMap::new();
+        for result in &survivors {
+            let key = normalize_recommendation(result.recommendation.as_deref());
+            buckets.entry(key).or_insert_with(Vec::new).push(result);
+        }
+
+        let (winning_recommendation, winners) = buckets
+            .into_iter()
+            .max_by(|(_, a), (_, b)| {
+                let weight_a: f32 = a.iter().map(|r| r.confidence).sum();
+                let weight_b: f32 = b.iter().map(|r| r.confidence).sum();
+                weight_a.partial_cmp(&weight_b).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .expect("survivors 非空，必有胜出分桶");
+
+        let total_weight: f32 = winners.iter().map(|r| r.confidence).sum();
+        let confidence = if total_weight > 0.0 {
+            total_weight / winners.len() as f32
+        } else {
+            0.0
+        };
+
+        let urgency = if total_weight > 0.0 {
+            (winners.iter().map(|r| r.urgency as f32 * r.confidence).sum::<f32>() / total_weight).round() as i32
+        } else {
+            winners.iter().map(|r| r.urgency).sum::<i32>() / winners.len() as i32
+        };
+
+        let contract_address = most_common_non_null(survivors.iter().map(|r| r.contract_address.as_deref()));
+        let token_name = most_common_non_null(survivors.iter().map(|r| r.token_name.as_deref()));
+        let chain = most_common_non_null(survivors.iter().map(|r| r.chain.as_deref()));
+
+        let reason = winners.iter()
+            .max_by(|a, b| a.confidence.partial_cmp(&b.confidence).unwrap_or(std::cmp::Ordering::Equal))
+            .and_then(|r| r.reason.clone());
+
+        let sources: Vec<String> = survivors.iter().map(|r| r.source.clone()).collect();
+        let raw_response = survivors.iter()
+            .map(|r| format!("[{}] {}", r.source, r.raw_response.as_deref().unwrap_or("")))
+            .collect::<Vec<_>>()
+            .join("\n---\n");
+
+        AnalysisResult {
+            is_relevant: true,
+            token_name,
+            contract_address,
+            chain,
+            recommendation: if winning_recommendation == "观望" { None } else { Some(winning_recommendation) },
+            reason,
+            confidence,
+            urgency,
+            source: format!("ensemble({})", sources.join("+")),
+            timestamp: chrono::Utc::now().timestamp(),
+            raw_response: Some(raw_response),
+        }
+    }
+
+    /// 退化路径：未达到法定人数时，采用置信度最高的单个结果
+    fn best_single(results: Vec<AnalysisResult>) -> Option<AnalysisResult> {
+        results.into_iter()
+            .max_by(|a, b| a.confidence.partial_cmp(&b.confidence).unwrap_or(std::cmp::Ordering::Equal))
+    }
+}
+
+#[async_trait]
+impl AIService for EnsembleService {
+    async fn analyze(&self, message: &str) -> Result<AnalysisResult, AIError> {
+        let deadline = self.timeout;
+        let mut pending = FuturesUnordered::new();
+
+        for service in &self.services {
+            let name = service.name();
+            pending.push(async move {
+                match tokio::time::timeout(deadline, service.analyze(message)).await {
+                    Ok(Ok(result)) => Some(result),
+                    Ok(Err(e)) => {
+                        warn!("Ensemble 成员 {} 分析失败: {}", name, e);
+                        None
+                    }
+                    Err(_) => {
+                        warn!("Ensemble 成员 {} 在截止时间内未返回", name);
+                        None
+                    }
+                }
+            });
+        }
+
+        let mut results = Vec::new();
+        while let Some(result) = pending.next().await {
+            if let Some(result) = result {
+                results.push(result);
+            }
+        }
+
+        debug!("Ensemble 收到 {}/{} 个成员响应", results.len(), self.services.len());
+
+        let received = results.len();
+        if received >= self.quorum {
+            info!("Ensemble 达到法定人数 ({}/{})，按加权投票合并", received, self.quorum);
+            Ok(self.merge(results))
+        } else if let Some(best) = Self::best_single(results) {
+            warn!("Ensemble 未达到法定人数 ({}/{})，退化为单一最高置信度结果: {}", received, self.quorum, best.source);
+            Ok(best)
+        } else {
+            Err(AIError::ApiError("所有 Ensemble 成员均未能在截止时间内返回结果".to_string()))
+        }
+    }
+
+    async fn health_check(&self) -> bool {
+        let mut healthy = 0;
+        for service in &self.services {
+            if service.health_check().await {
+                healthy += 1;
+            }
+        }
+        healthy >= self.quorum
+    }
+
+    fn name(&self) -> String {
+        format!("Ensemble Service ({} 个成员)", self.services.len())
+    }
+
+    fn provider(&self) -> AIProvider {
+        AIProvider::Ensemble
+    }
+}
+
+/// 将买入/卖出/持有的各种别名归一化，便于投票分桶
+fn normalize_recommendation(recommendation: Option<&str>) -> String {
+    match recommendation.map(|s| s.to_lowercase()) {
+        Some(s) if s.contains("买入") || s.contains("buy") => "买入".to_string(),
+        Some(s) if s.contains("卖出") || s.contains("sell") => "卖出".to_string(),
+        Some(s) if s.contains("持有") || s.contains("hold") => "持有".to_string(),
+        _ => "观望".to_string(),
+    }
+}
+
+/// 在一组可选字符串中找出出现次数最多的非空值
+fn most_common_non_null<'a, I: Iterator<Item = Option<&'a str>>>(values: I) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for value in values.flatten() {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+
+    counts.into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(value, _)| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(recommendation: &str, confidence: f32, urgency: i32, source: &str) -> AnalysisResult {
+        AnalysisResult {
+            is_relevant: true,
+            token_name: Some("TESTTOKEN".to_string()),
+            contract_address: Some("0xabc".to_string()),
+            chain: Some("evm".to_string()),
+            recommendation: Some(recommendation.to_string()),
+            reason: Some(format!("reason-{}", source)),
+            confidence,
+            urgency,
+            source: source.to_string(),
+            timestamp: 0,
+            raw_response: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_no_survivors() {
+        let ensemble = EnsembleService {
+            services: Vec::new(),
+            quorum: 1,
+            timeout: Duration::from_secs(1),
+        };
+
+        let mut irrelevant = sample("观望", 0.9, 1, "a");
+        irrelevant.is_relevant = false;
+        let merged = ensemble.merge(vec![irrelevant]);
+        assert!(!merged.is_relevant);
+    }
+
+    #[test]
+    fn test_merge_tie_picks_one_of_the_tied_buckets() {
+        let ensemble = EnsembleService {
+            services: Vec::new(),
+            quorum: 1,
+            timeout: Duration::from_secs(1),
+        };
+
+        // 两个推荐分桶权重相同（各自只有一票，置信度相同），
+        // HashMap 迭代顺序不保证，max_by 在相等时取后者——
+        // 这里断言胜出的分桶一定是二者之一，而不依赖具体是哪个，
+        // 避免测试本身假设了未规定的迭代顺序
+        let results = vec![sample("买入", 0.8, 2, "a"), sample("卖出", 0.8, 4, "b")];
+        let merged = ensemble.merge(results);
+
+        assert!(merged.is_relevant);
+        assert!(merged.recommendation == Some("买入".to_string()) || merged.recommendation == Some("卖出".to_string()));
+        assert_eq!(merged.confidence, 0.8);
+    }
+
+    #[test]
+    fn test_most_common_non_null_tie_returns_one_of_the_tied_values() {
+        let values = vec![Some("a"), Some("b")];
+        let result = most_common_non_null(values.into_iter());
+
+        assert!(result == Some("a".to_string()) || result == Some("b".to_string()));
+    }
+
+    #[test]
+    fn test_most_common_non_null_majority_wins() {
+        let values = vec![Some("a"), Some("b"), Some("a")];
+        let result = most_common_non_null(values.into_iter());
+
+        assert_eq!(result, Some("a".to_string()));
+    }
+}
@@ -0,0 +1,368 @@
+//! 可插拔的 Token 名称 / 多链合约地址提取器
+//!
+//! 旧版 `extract_contract_address` 只认 EVM 的 `0x` + 40 位十六进制地址，
+//! 遗漏了 Solana、Tron 等链上格式完全不同的地址，`extract_token_name`
+//! 也只是粗糙的大写单词启发式。这里把提取逻辑拆成若干实现 [`Extractor`]
+//! trait 的独立提取器，每个提取器按自己的规则打分置信度，
+//! `run_pipeline` 汇总所有候选项，选出置信度最高的地址和名称。
+
+use regex::Regex;
+
+/// 单次提取得到的候选项：要么是某条链上的地址，要么是一个候选名称
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Candidate {
+    Address { chain: &'static str, value: String },
+    Name(String),
+}
+
+/// 提取器统一接口
+pub trait Extractor {
+    /// 提取器名称，用于日志
+    fn name(&self) -> &'static str;
+
+    /// 从文本中找出该提取器能识别的候选项，每个候选项附带置信度
+    /// （0.0-1.0），供流水线比较选出最佳结果
+    fn extract(&self, text: &str) -> Vec<(Candidate, f32)>;
+}
+
+/// EVM（Ethereum/BSC/Polygon 等）地址提取器
+///
+/// 匹配 `0x` + 40 位十六进制；若地址大小写混合，按 EIP-55 校验和规则
+/// 验证，通过则视为高置信度，不通过则视为可能是伪造/拼错的地址。
+/// 全大写或全小写的地址没有编码校验和信息，无法验证，给中等置信度。
+pub struct EvmExtractor;
+
+impl Extractor for EvmExtractor {
+    fn name(&self) -> &'static str {
+        "evm"
+    }
+
+    fn extract(&self, text: &str) -> Vec<(Candidate, f32)> {
+        let re = match Regex::new(r"0x[a-fA-F0-9]{40}") {
+            Ok(re) => re,
+            Err(_) => return Vec::new(),
+        };
+
+        re.find_iter(text)
+            .map(|m| {
+                let address = m.as_str().to_string();
+                let confidence = evm_confidence(&address);
+                (Candidate::Address { chain: "evm", value: address }, confidence)
+            })
+            .collect()
+    }
+}
+
+/// 根据大小写混合情况与 EIP-55 校验和判断 EVM 地址的可信度
+fn evm_confidence(address: &str) -> f32 {
+    let body = &address[2..];
+    let has_upper = body.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = body.chars().any(|c| c.is_ascii_lowercase());
+
+    if !has_upper || !has_lower {
+        // 全大写或全小写，没有编码校验和信息，无法验证
+        return 0.7;
+    }
+
+    if eip55::is_checksummed(address) {
+        0.95
+    } else {
+        0.5
+    }
+}
+
+/// Solana 地址提取器：base58 编码，32-44 个字符
+///
+/// base58 字母表本身没有链区分能力，置信度相对保守
+pub struct SolanaExtractor;
+
+impl Extractor for SolanaExtractor {
+    fn name(&self) -> &'static str {
+        "solana"
+    }
+
+    fn extract(&self, text: &str) -> Vec<(Candidate, f32)> {
+        let re = match Regex::new(r"\b[1-9A-HJ-NP-Za-km-z]{32,44}\b") {
+            Ok(re) => re,
+            Err(_) => return Vec::new(),
+        };
+
+        re.find_iter(text)
+            .map(|m| {
+                let value = m.as_str().to_string();
+                (Candidate::Address { chain: "solana", value }, 0.5)
+            })
+            .collect()
+    }
+}
+
+/// Tron 地址提取器：`T` 开头 + 33 个 base58 字符（共 34 位）
+pub struct TronExtractor;
+
+impl Extractor for TronExtractor {
+    fn name(&self) -> &'static str {
+        "tron"
+    }
+
+    fn extract(&self, text: &str) -> Vec<(Candidate, f32)> {
+        let re = match Regex::new(r"\bT[1-9A-HJ-NP-Za-km-z]{33}\b") {
+            Ok(re) => re,
+            Err(_) => return Vec::new(),
+        };
+
+        re.find_iter(text)
+            .map(|m| {
+                let value = m.as_str().to_string();
+                (Candidate::Address { chain: "tron", value }, 0.75)
+            })
+            .collect()
+    }
+}
+
+/// `$SYMBOL` 风格 Ticker 提取器，识别 meme token 常见的美元符号前缀写法
+pub struct TickerExtractor;
+
+impl Extractor for TickerExtractor {
+    fn name(&self) -> &'static str {
+        "ticker"
+    }
+
+    fn extract(&self, text: &str) -> Vec<(Candidate, f32)> {
+        let re = match Regex::new(r"\$([A-Za-z][A-Za-z0-9]{1,9})\b") {
+            Ok(re) => re,
+            Err(_) => return Vec::new(),
+        };
+
+        re.captures_iter(text)
+            .filter_map(|c| c.get(1))
+            .map(|m| (Candidate::Name(m.as_str().to_uppercase()), 0.6))
+            .collect()
+    }
+}
+
+/// 流水线汇总结果
+#[derive(Debug, Clone, Default)]
+pub struct ExtractionResult {
+    pub token_name: Option<String>,
+    pub contract_address: Option<String>,
+    pub chain: Option<String>,
+}
+
+/// 依次跑完所有内置提取器，取地址候选项与名称候选项中置信度最高的各一个
+pub fn run_pipeline(text: &str) -> ExtractionResult {
+    let extractors: Vec<Box<dyn Extractor>> = vec![
+        Box::new(EvmExtractor),
+        Box::new(SolanaExtractor),
+        Box::new(TronExtractor),
+        Box::new(TickerExtractor),
+    ];
+
+    let mut best_address: Option<(&'static str, String, f32)> = None;
+    let mut best_name: Option<(String, f32)> = None;
+
+    for extractor in &extractors {
+        for (candidate, confidence) in extractor.extract(text) {
+            match candidate {
+                Candidate::Address { chain, value } => {
+                    if best_address.as_ref().map_or(true, |(_, _, c)| confidence > *c) {
+                        best_address = Some((chain, value, confidence));
+                    }
+                }
+                Candidate::Name(value) => {
+                    if best_name.as_ref().map_or(true, |(_, c)| confidence > *c) {
+                        best_name = Some((value, confidence));
+                    }
+                }
+            }
+        }
+    }
+
+    ExtractionResult {
+        token_name: best_name.map(|(value, _)| value),
+        chain: best_address.as_ref().map(|(chain, _, _)| chain.to_string()),
+        contract_address: best_address.map(|(_, value, _)| value),
+    }
+}
+
+/// 给定一个已经确定完整的地址字符串（例如模型结构化返回的
+/// `contract_address`），直接按格式判断所属链，不做置信度打分
+pub fn detect_chain_for_address(address: &str) -> Option<&'static str> {
+    if Regex::new(r"^0x[a-fA-F0-9]{40}$").ok()?.is_match(address) {
+        return Some("evm");
+    }
+    if Regex::new(r"^T[1-9A-HJ-NP-Za-km-z]{33}$").ok()?.is_match(address) {
+        return Some("tron");
+    }
+    if Regex::new(r"^[1-9A-HJ-NP-Za-km-z]{32,44}$").ok()?.is_match(address) {
+        return Some("solana");
+    }
+    None
+}
+
+/// 纯 Rust 实现的 Keccak-256（原始 Keccak 填充 `0x01`，不同于 SHA3 的
+/// `0x06`），仅用于 EVM 地址的 EIP-55 校验和验证，避免引入额外依赖
+mod eip55 {
+    const RC: [u64; 24] = [
+        0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+        0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+        0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+        0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+        0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+        0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+    ];
+
+    const ROTC: [[u32; 5]; 5] = [
+        [0, 36, 3, 41, 18],
+        [1, 44, 10, 45, 2],
+        [62, 6, 43, 15, 61],
+        [28, 55, 25, 21, 56],
+        [27, 20, 39, 8, 14],
+    ];
+
+    fn keccak_f1600(state: &mut [u64; 25]) {
+        for round in 0..24 {
+            // theta
+            let mut c = [0u64; 5];
+            for x in 0..5 {
+                c[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+            }
+            let mut d = [0u64; 5];
+            for x in 0..5 {
+                d[x] = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            }
+            for x in 0..5 {
+                for y in 0..5 {
+                    state[x + 5 * y] ^= d[x];
+                }
+            }
+
+            // rho + pi
+            let mut b = [0u64; 25];
+            for x in 0..5 {
+                for y in 0..5 {
+                    let new_x = y;
+                    let new_y = (2 * x + 3 * y) % 5;
+                    b[new_x + 5 * new_y] = state[x + 5 * y].rotate_left(ROTC[x][y]);
+                }
+            }
+
+            // chi
+            for y in 0..5 {
+                for x in 0..5 {
+                    state[x + 5 * y] = b[x + 5 * y] ^ ((!b[(x + 1) % 5 + 5 * y]) & b[(x + 2) % 5 + 5 * y]);
+                }
+            }
+
+            // iota
+            state[0] ^= RC[round];
+        }
+    }
+
+    fn keccak256(input: &[u8]) -> [u8; 32] {
+        const RATE: usize = 136; // 1088 位 / 8
+
+        let mut state = [0u64; 25];
+        let mut padded = input.to_vec();
+        padded.push(0x01);
+        while padded.len() % RATE != 0 {
+            padded.push(0x00);
+        }
+        let last = padded.len() - 1;
+        padded[last] |= 0x80;
+
+        for chunk in padded.chunks(RATE) {
+            for (i, word) in chunk.chunks(8).enumerate() {
+                let mut bytes = [0u8; 8];
+                bytes[..word.len()].copy_from_slice(word);
+                state[i] ^= u64::from_le_bytes(bytes);
+            }
+            keccak_f1600(&mut state);
+        }
+
+        let mut output = [0u8; 32];
+        for i in 0..4 {
+            output[i * 8..i * 8 + 8].copy_from_slice(&state[i].to_le_bytes());
+        }
+        output
+    }
+
+    /// 按 EIP-55 规则重新计算地址的大小写并与输入比较
+    pub fn is_checksummed(address: &str) -> bool {
+        let body = &address[2..];
+        let lower = body.to_lowercase();
+        let hash = keccak256(lower.as_bytes());
+        let hash_hex: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+        let hash_bytes = hash_hex.as_bytes();
+
+        let expected: String = lower.chars().enumerate().map(|(i, c)| {
+            if c.is_ascii_digit() {
+                c
+            } else {
+                let nibble = (hash_bytes[i] as char).to_digit(16).unwrap_or(0);
+                if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+            }
+        }).collect();
+
+        expected == body
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_eip55_known_vectors() {
+            // 来自 EIP-55 规范的官方测试向量
+            let vectors = [
+                "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+                "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+                "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+                "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+            ];
+
+            for address in vectors {
+                assert!(is_checksummed(address), "{} 应通过 EIP-55 校验", address);
+            }
+        }
+
+        #[test]
+        fn test_eip55_rejects_tampered_case() {
+            let tampered = "0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+            assert!(!is_checksummed(tampered), "大小写被篡改的地址不应通过校验");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evm_extractor_detects_checksummed_address() {
+        let text = "合约地址: 0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let result = run_pipeline(text);
+        assert_eq!(result.chain.as_deref(), Some("evm"));
+        assert_eq!(result.contract_address.as_deref(), Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"));
+    }
+
+    #[test]
+    fn test_tron_extractor() {
+        let text = "Tron 合约: T123456789ABCDEFGHJKLMNPQRSTUVWXYZ 快上车";
+        let result = run_pipeline(text);
+        assert_eq!(result.chain.as_deref(), Some("tron"));
+    }
+
+    #[test]
+    fn test_ticker_extractor() {
+        let text = "$PEPE 马上要火了";
+        let result = run_pipeline(text);
+        assert_eq!(result.token_name.as_deref(), Some("PEPE"));
+    }
+
+    #[test]
+    fn test_detect_chain_for_address() {
+        assert_eq!(detect_chain_for_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"), Some("evm"));
+        assert_eq!(detect_chain_for_address("T123456789ABCDEFGHJKLMNPQRSTUVWXYZ"), Some("tron"));
+        assert_eq!(detect_chain_for_address("not an address"), None);
+    }
+}
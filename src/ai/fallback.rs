@@ -0,0 +1,167 @@
+use super::models::{AIProvider, AnalysisResult};
+use super::{AIError, AIService};
+use crate::config::AIConfig;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// 故障转移链中的一个成员，附带近期延迟的滑动均值
+struct Member {
+    service: Box<dyn AIService>,
+    /// 近期延迟的指数滑动均值（毫秒），0 表示尚未探测过
+    avg_latency_ms: AtomicU64,
+}
+
+impl Member {
+    fn record_latency(&self, elapsed: Duration) {
+        let sample = elapsed.as_millis() as u64;
+        let _ = self.avg_latency_ms.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+            Some(if old == 0 { sample } else { (old * 3 + sample) / 4 })
+        });
+    }
+}
+
+/// 多提供商故障转移服务
+///
+/// 按配置顺序持有一条提供商链（例如 Ollama 主、Kimi/OpenAI 备用），
+/// `analyze` 依次尝试，遇到 `NetworkError`/`ApiError`/`TimeoutError`
+/// 就切换到下一个，直到成功或全部失败；其他错误（如配置错误）视为
+/// 不可恢复，直接向上传播。同时记录每个成员的近期延迟，下一次请求
+/// 按延迟从低到高重新排序尝试顺序，让探测到更快的健康后端逐渐被
+/// 优先使用。
+pub struct FallbackAIService {
+    members: Vec<Member>,
+}
+
+impl FallbackAIService {
+    /// 创建故障转移服务，`providers` 为有序的提供商名称列表
+    pub fn new(ai_config: &AIConfig, providers: &[String]) -> Result<Self, AIError> {
+        if providers.is_empty() {
+            return Err(AIError::ConfigError("故障转移链至少需要一个提供商".to_string()));
+        }
+
+        let mut members = Vec::new();
+        for provider in providers {
+            let service: Box<dyn AIService> = match provider.as_str() {
+                "ollama" | "local" => Box::new(super::local::OllamaService::new(ai_config)?),
+                "kimi" => Box::new(super::kimi::KimiService::new(ai_config)?),
+                "openai" => Box::new(super::openai::OpenAIService::new(ai_config)?),
+                other => return Err(AIError::UnsupportedProvider(other.to_string())),
+            };
+            members.push(Member {
+                service,
+                avg_latency_ms: AtomicU64::new(0),
+            });
+        }
+
+        Ok(Self { members })
+    }
+
+    /// 是否应该切换到故障转移链中的下一个成员
+    fn is_failover_error(err: &AIError) -> bool {
+        matches!(err, AIError::NetworkError(_) | AIError::ApiError(_) | AIError::TimeoutError)
+    }
+
+    /// 按已记录的平均延迟从快到慢排序成员下标，尚未探测过（延迟为 0）
+    /// 的成员保持原有的配置顺序排在最前
+    fn ordered_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.members.len()).collect();
+        indices.sort_by_key(|&i| {
+            let latency = self.members[i].avg_latency_ms.load(Ordering::Relaxed);
+            // (是否已探测过, 延迟) ：未探测过的排在前面（false < true），
+            // 已探测过的再按延迟从低到高排序；同为未探测时 sort_by_key
+            // 是稳定排序，相对顺序保持配置中的原始顺序不变
+            (latency != 0, latency)
+        });
+        indices
+    }
+}
+
+#[async_trait]
+impl AIService for FallbackAIService {
+    async fn analyze(&self, message: &str) -> Result<AnalysisResult, AIError> {
+        let mut last_err = None;
+
+        for idx in self.ordered_indices() {
+            let member = &self.members[idx];
+            let started = Instant::now();
+
+            match member.service.analyze(message).await {
+                Ok(result) => {
+                    member.record_latency(started.elapsed());
+                    return Ok(result);
+                }
+                Err(e) if Self::is_failover_error(&e) => {
+                    warn!("故障转移链成员 {} 失败，切换下一个提供商: {}", member.service.name(), e);
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| AIError::ApiError("故障转移链中没有可用的提供商".to_string())))
+    }
+
+    async fn health_check(&self) -> bool {
+        for member in &self.members {
+            if member.service.health_check().await {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn name(&self) -> String {
+        let chain = self.members.iter().map(|m| m.service.name()).collect::<Vec<_>>().join(" -> ");
+        format!("Fallback Chain ({})", chain)
+    }
+
+    fn provider(&self) -> AIProvider {
+        AIProvider::Fallback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::models::AnalysisResult;
+
+    /// 测试用假 AI 服务：不发起任何真实网络请求
+    struct FakeAIService;
+
+    #[async_trait]
+    impl AIService for FakeAIService {
+        async fn analyze(&self, _message: &str) -> Result<AnalysisResult, AIError> {
+            Ok(AnalysisResult::empty())
+        }
+
+        async fn health_check(&self) -> bool {
+            true
+        }
+
+        fn name(&self) -> String {
+            "fake-ai-service".to_string()
+        }
+
+        fn provider(&self) -> AIProvider {
+            AIProvider::Kimi
+        }
+    }
+
+    fn member(avg_latency_ms: u64) -> Member {
+        Member {
+            service: Box::new(FakeAIService),
+            avg_latency_ms: AtomicU64::new(avg_latency_ms),
+        }
+    }
+
+    #[test]
+    fn test_ordered_indices_sorts_untested_members_first_in_original_order() {
+        let service = FallbackAIService {
+            members: vec![member(50), member(0), member(10), member(0)],
+        };
+
+        assert_eq!(service.ordered_indices(), vec![1, 3, 2, 0]);
+    }
+}
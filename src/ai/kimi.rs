@@ -1,11 +1,12 @@
 use super::{AIService, AIError};
+use super::metrics::{ProviderPricing, UsageMetrics, UsageSnapshot};
 use super::models::AnalysisResult;
 use async_trait::async_trait;
 use config::{AIConfig, KimiConfig};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info};
 
 /// Kimi API 服务实现
@@ -15,6 +16,7 @@ pub struct KimiService {
     timeout: Duration,
     prompt_template: String,
     max_retries: u32,
+    metrics: UsageMetrics,
 }
 
 impl KimiService {
@@ -23,17 +25,32 @@ impl KimiService {
         let kimi_config = ai_config.kimi.as_ref()
             .ok_or_else(|| AIError::ConfigError("Kimi 配置未找到".to_string()))?;
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(ai_config.timeout_seconds))
+        let mut client_builder = Client::builder()
+            .timeout(Duration::from_secs(ai_config.timeout_seconds));
+
+        if let Some(proxy_url) = &ai_config.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| AIError::ConfigError(format!("ai.proxy 无效: {}", e)))?;
+            client_builder = client_builder.proxy(proxy);
+            info!("Kimi 客户端已启用代理: {}", proxy_url);
+        }
+
+        let client = client_builder
             .build()
             .map_err(|e| AIError::NetworkError(e.to_string()))?;
 
+        let pricing = ProviderPricing {
+            input_per_1k: kimi_config.input_price_per_1k,
+            output_per_1k: kimi_config.output_price_per_1k,
+        };
+
         Ok(Self {
             client,
             config: kimi_config.clone(),
             timeout: Duration::from_secs(ai_config.timeout_seconds),
             prompt_template: ai_config.prompt_template.clone(),
             max_retries: ai_config.max_retries,
+            metrics: UsageMetrics::new("kimi", pricing),
         })
     }
 
@@ -80,9 +97,13 @@ impl KimiService {
 #[async_trait]
 impl AIService for KimiService {
     async fn analyze(&self, message: &str) -> Result<AnalysisResult, AIError> {
-        debug!("使用 Kimi 分析消息: {}", message[..message.len().min(50)].to_string());
+        // UTF-8安全的字符截断
+        let preview: String = message.chars().take(50).collect();
+        debug!("使用 Kimi 分析消息: {}", preview);
 
-        // 构建请求体
+        // 构建请求体：通过 tools + tool_choice 强制模型调用
+        // report_token_analysis，而不是在文本里自由返回 JSON
+        let tool = super::report_token_analysis_tool();
         let request_body = serde_json::json!({
             "model": self.config.model,
             "messages": [
@@ -97,16 +118,20 @@ impl AIService for KimiService {
             ],
             "temperature": 0.3,
             "max_tokens": 500,
-            "stream": false
+            "stream": false,
+            "tools": [tool],
+            "tool_choice": {"type": "function", "function": {"name": "report_token_analysis"}}
         });
 
         debug!("发送请求到 Kimi API...");
 
         // 发送请求并处理重试
+        let started = Instant::now();
         let mut last_error = None;
         for attempt in 0..=self.max_retries {
             if attempt > 0 {
                 info!("第 {} 次重试...", attempt);
+                self.metrics.record_retry();
                 tokio::time::sleep(Duration::from_secs(2_u64.pow(attempt - 1))).await;
             }
 
@@ -127,11 +152,23 @@ impl AIService for KimiService {
                             Ok(result) => {
                                 debug!("成功收到 Kimi API 响应");
 
-                                // 提取 content
+                                let prompt_tokens = result["usage"]["prompt_tokens"].as_u64().unwrap_or(0) as u32;
+                                let completion_tokens = result["usage"]["completion_tokens"].as_u64().unwrap_or(0) as u32;
+                                self.metrics.record_success(prompt_tokens, completion_tokens, started.elapsed());
+
+                                // 优先走 tool_calls 结构化路径；旧模型/未命中
+                                // tool_choice 时回退到文本解析
+                                if let Some(arguments) = result["choices"][0]["message"]["tool_calls"][0]["function"]["arguments"].as_str() {
+                                    debug!("Kimi 返回 tool_calls，使用结构化解析");
+                                    return super::analysis_result_from_tool_call(arguments, "kimi")
+                                        .map_err(|e| AIError::ParseError(e.to_string()));
+                                }
+
                                 let content = result["choices"][0]["message"]["content"]
                                     .as_str()
                                     .ok_or_else(|| AIError::ParseError("响应中没有 content 字段".to_string()))?;
 
+                                debug!("Kimi 未返回 tool_calls，回退到文本解析");
                                 return self.parse_response(content, message);
                             }
                             Err(e) => {
@@ -153,6 +190,7 @@ impl AIService for KimiService {
             }
         }
 
+        self.metrics.record_error(started.elapsed());
         Err(last_error.unwrap_or_else(|| AIError::ApiError("所有重试均失败".to_string())))
     }
 
@@ -193,13 +231,17 @@ impl AIService for KimiService {
         }
     }
 
-    fn name(&self) -> &str {
-        &format!("Kimi API Service ({})", self.config.model)
+    fn name(&self) -> String {
+        format!("Kimi API Service ({})", self.config.model)
     }
 
     fn provider(&self) -> AIProvider {
         AIProvider::Kimi
     }
+
+    fn usage_snapshot(&self) -> Option<UsageSnapshot> {
+        Some(self.metrics.snapshot())
+    }
 }
 
 // Kimi API 响应结构
@@ -1,11 +1,12 @@
 use super::{AIService, AIError};
+use super::metrics::{ProviderPricing, UsageMetrics, UsageSnapshot};
 use super::models::{AnalysisResult, AIProvider};
 use async_trait::async_trait;
-use crate::config::{AIConfig, OllamaConfig};
+use crate::config::{AIConfig, OllamaConfig, OllamaOptionsConfig};
 use reqwest::Client;
 use serde::Deserialize;
 use serde_json::Value;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info};
 
 /// Ollama 本地服务实现
@@ -14,7 +15,10 @@ pub struct OllamaService {
     config: OllamaConfig,
     timeout: Duration,
     prompt_template: String,
+    /// `api_mode = "chat"` 时发送的系统提示词
+    system_prompt: String,
     max_retries: u32,
+    metrics: UsageMetrics,
 }
 
 impl OllamaService {
@@ -23,25 +27,64 @@ impl OllamaService {
         let ollama_config = ai_config.ollama.as_ref()
             .ok_or_else(|| AIError::ConfigError("Ollama 配置未找到".to_string()))?;
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(ai_config.timeout_seconds))
+        let mut client_builder = Client::builder()
+            .timeout(Duration::from_secs(ai_config.timeout_seconds));
+
+        if let Some(proxy_url) = &ai_config.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| AIError::ConfigError(format!("ai.proxy 无效: {}", e)))?;
+            client_builder = client_builder.proxy(proxy);
+            info!("Ollama 客户端已启用代理: {}", proxy_url);
+        }
+
+        let client = client_builder
             .build()
             .map_err(|e| AIError::NetworkError(e.to_string()))?;
 
+        let system_prompt = ollama_config.system_prompt.clone()
+            .unwrap_or_else(Self::default_system_prompt);
+
         Ok(Self {
             client,
             config: ollama_config.clone(),
             timeout: Duration::from_secs(ai_config.timeout_seconds),
             prompt_template: ai_config.prompt_template.clone(),
+            system_prompt,
             max_retries: ai_config.max_retries,
+            // 本地模型不计费，成本单价按 0 处理，但仍跟踪 token/请求速率
+            metrics: UsageMetrics::new("ollama", ProviderPricing::default()),
         })
     }
 
-    /// 构建完整的提示词
+    /// 构建用户提示词（`generate` 模式下即完整 prompt；`chat` 模式下
+    /// 作为 user 角色的消息内容，系统指令由 `system_prompt` 单独携带）
     fn build_prompt(&self, message: &str) -> String {
-        // 在 Ollama 中使用模板直接包含系统提示
         self.prompt_template.replace("{}", message)
     }
+
+    /// `chat` 模式缺省系统提示词：与上游 prompt_template 中的指令格式
+    /// 对齐，要求模型以 JSON 形式返回结构化分析结果
+    fn default_system_prompt() -> String {
+        r#"你是一名专业的加密货币交易信息分析师，负责判断 Telegram 消息是否在讨论 meme token 交易信息。
+
+如果消息相关，以 JSON 格式返回：
+{"is_relevant": true, "token_name": "...", "contract_address": "...", "recommendation": "买入/卖出/持有", "reason": "...", "confidence": 0.85, "urgency": 7}
+
+如果消息不相关，返回：{"is_relevant": false}
+
+只返回 JSON，不要包含其他文本。confidence 取值 0.0-1.0，urgency 取值 1-10。"#.to_string()
+    }
+
+    /// 根据 `OllamaOptionsConfig` 构建请求体中的 `options` 对象
+    fn build_options(options: &OllamaOptionsConfig) -> Value {
+        serde_json::json!({
+            "temperature": options.temperature,
+            "top_p": options.top_p,
+            "repeat_penalty": options.repeat_penalty,
+            "num_ctx": options.num_ctx,
+            "num_predict": options.num_predict,
+        })
+    }
 }
 
 #[async_trait]
@@ -51,30 +94,45 @@ impl AIService for OllamaService {
         let preview: String = message.chars().take(50).collect();
         debug!("使用 Ollama 本地模型分析消息: {}", preview);
 
-        // 构建请求体
-        let request_body = serde_json::json!({
-            "model": self.config.model,
-            "prompt": self.build_prompt(message),
-            "stream": false,
-            "options": {
-                "temperature": 0.3,
-                "top_p": 0.9,
-                "repeat_penalty": 1.1,
-            }
-        });
+        // legacy 模式走 /api/generate 的单一 prompt 拼接；默认的 chat 模式
+        // 走 /api/chat，system/user 角色分离
+        let use_chat = self.config.api_mode != "generate";
+        let endpoint_path = if use_chat { "chat" } else { "generate" };
+        let options = Self::build_options(&self.config.options);
+
+        let request_body = if use_chat {
+            serde_json::json!({
+                "model": self.config.model,
+                "messages": [
+                    {"role": "system", "content": self.system_prompt},
+                    {"role": "user", "content": self.build_prompt(message)},
+                ],
+                "stream": false,
+                "options": options,
+            })
+        } else {
+            serde_json::json!({
+                "model": self.config.model,
+                "prompt": self.build_prompt(message),
+                "stream": false,
+                "options": options,
+            })
+        };
 
-        debug!("发送请求到 Ollama: {}", self.config.api_endpoint);
+        debug!("发送请求到 Ollama: {}/api/{}", self.config.api_endpoint, endpoint_path);
 
         // 发送请求并处理重试
+        let started = Instant::now();
         let mut last_error = None;
         for attempt in 0..=self.max_retries {
             if attempt > 0 {
                 info!("第 {} 次重试...", attempt);
+                self.metrics.record_retry();
                 tokio::time::sleep(Duration::from_secs(2_u64.pow(attempt - 1))).await;
             }
 
             match self.client
-                .post(format!("{}/api/generate", self.config.api_endpoint))
+                .post(format!("{}/api/{}", self.config.api_endpoint, endpoint_path))
                 .json(&request_body)
                 .timeout(self.timeout)
                 .send()
@@ -88,12 +146,20 @@ impl AIService for OllamaService {
                             Ok(result) => {
                                 debug!("成功收到 Ollama 本地模型响应");
 
-                                // Ollama 的响应在 "response" 字段
-                                let content = result["response"]
-                                    .as_str()
-                                    .ok_or_else(|| {
-                                        AIError::ParseError("响应中没有 response 字段".to_string())
-                                    })?;
+                                // Ollama 在 "prompt_eval_count"/"eval_count" 中返回 token 数
+                                let prompt_tokens = result["prompt_eval_count"].as_u64().unwrap_or(0) as u32;
+                                let completion_tokens = result["eval_count"].as_u64().unwrap_or(0) as u32;
+                                self.metrics.record_success(prompt_tokens, completion_tokens, started.elapsed());
+
+                                // chat 模式响应在 "message.content"，generate 模式在 "response"
+                                let content = if use_chat {
+                                    result["message"]["content"].as_str()
+                                } else {
+                                    result["response"].as_str()
+                                }
+                                .ok_or_else(|| {
+                                    AIError::ParseError("响应中没有内容字段".to_string())
+                                })?;
 
                                 return self.parse_response(content, message);
                             }
@@ -117,39 +183,20 @@ impl AIService for OllamaService {
             }
         }
 
+        self.metrics.record_error(started.elapsed());
         Err(last_error.unwrap_or_else(|| AIError::ApiError("所有重试均失败".to_string())))
     }
 
     async fn health_check(&self) -> bool {
         debug!("检查 Ollama 服务健康状态...");
 
-        let request_body = serde_json::json!({
-            "model": self.config.model,
-            "prompt": "Hi",
-            "stream": false,
-            "options": {
-                "max_tokens": 1,
-            }
-        });
-
-        match self.client
-            .post(format!("{}/api/generate", self.config.api_endpoint))
-            .json(&request_body)
-            .timeout(Duration::from_secs(10))
-            .send()
-            .await
-        {
-            Ok(response) => {
-                let is_success = response.status().is_success();
-                if is_success {
-                    info!("✓ Ollama 本地服务健康检查通过");
-                } else {
-                    error!("✗ Ollama 健康检查失败: HTTP {}", response.status());
-                }
-                is_success
+        match self.ensure_model_ready().await {
+            Ok(()) => {
+                info!("✓ Ollama 本地服务健康检查通过，模型 {} 已就绪", self.config.model);
+                true
             }
             Err(e) => {
-                error!("✗ 无法连接 Ollama 服务: {}", e);
+                error!("✗ Ollama 健康检查失败: {}", e);
                 error!("  请确保: 1) Ollama 已安装 2) 服务正在运行 3) 模型已下载");
                 false
             }
@@ -163,6 +210,98 @@ impl AIService for OllamaService {
     fn provider(&self) -> AIProvider {
         AIProvider::Ollama
     }
+
+    fn usage_snapshot(&self) -> Option<UsageSnapshot> {
+        Some(self.metrics.snapshot())
+    }
+
+    /// 以 `"stream": true` 调用 `/api/generate`，按行解析
+    /// `{"response":"<chunk>","done":false}` 格式的增量输出，每行回调一次
+    /// `on_chunk`，最后一行（`done:true`）携带完整的用量统计
+    async fn analyze_streaming(
+        &self,
+        message: &str,
+        mut on_chunk: Box<dyn FnMut(&str) + Send>,
+    ) -> Result<AnalysisResult, AIError> {
+        use futures::stream::StreamExt;
+
+        let preview: String = message.chars().take(50).collect();
+        debug!("使用 Ollama 本地模型流式分析消息: {}", preview);
+
+        let started = Instant::now();
+        let request_body = serde_json::json!({
+            "model": self.config.model,
+            "prompt": self.build_prompt(message),
+            "stream": true,
+            "options": Self::build_options(&self.config.options),
+        });
+
+        let response = self.client
+            .post(format!("{}/api/generate", self.config.api_endpoint))
+            .json(&request_body)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(|e| AIError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "无法读取错误信息".to_string());
+            error!("Ollama 流式请求返回错误 {}: {}", status, error_text);
+            self.metrics.record_error(started.elapsed());
+            return Err(AIError::ApiError(format!("HTTP {}: {}", status, error_text)));
+        }
+
+        let mut stream = response.bytes_stream();
+        // 按行攒 buffer：一次 `bytes_stream` 的 chunk 不保证对齐 JSON 行边界，
+        // 可能把一行拆成两半，需要自己找换行符
+        let mut line_buf = String::new();
+        let mut full_content = String::new();
+        let mut prompt_tokens = 0u32;
+        let mut completion_tokens = 0u32;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| AIError::NetworkError(e.to_string()))?;
+            line_buf.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(newline_pos) = line_buf.find('\n') {
+                let line = line_buf[..newline_pos].trim().to_string();
+                line_buf.drain(..=newline_pos);
+
+                if line.is_empty() {
+                    continue;
+                }
+
+                let parsed: OllamaResponse = serde_json::from_str(&line)
+                    .map_err(|e| AIError::ParseError(format!("解析 Ollama 流式响应行失败: {}", e)))?;
+
+                full_content.push_str(&parsed.response);
+                on_chunk(&parsed.response);
+
+                if parsed.done {
+                    if let Ok(value) = serde_json::from_str::<Value>(&line) {
+                        prompt_tokens = value["prompt_eval_count"].as_u64().unwrap_or(0) as u32;
+                        completion_tokens = value["eval_count"].as_u64().unwrap_or(0) as u32;
+                    }
+                }
+            }
+        }
+
+        // 兜底：如果最后一行没有以换行符结尾，line_buf 里还会剩一段
+        let trailing = line_buf.trim();
+        if !trailing.is_empty() {
+            if let Ok(parsed) = serde_json::from_str::<OllamaResponse>(trailing) {
+                full_content.push_str(&parsed.response);
+                on_chunk(&parsed.response);
+            }
+        }
+
+        self.metrics.record_success(prompt_tokens, completion_tokens, started.elapsed());
+        debug!("Ollama 流式响应接收完成，共 {} 字符", full_content.len());
+
+        self.parse_response(&full_content, message)
+    }
 }
 
 impl OllamaService {
@@ -172,6 +311,111 @@ impl OllamaService {
         parse_analysis_response(content, original_message, "local")
             .map_err(|e| AIError::ParseError(e.to_string()))
     }
+
+    /// 调用 `GET /api/tags` 获取本地已安装的模型列表
+    async fn list_models(&self) -> Result<Vec<OllamaModelInfo>, AIError> {
+        let response = self.client
+            .get(format!("{}/api/tags", self.config.api_endpoint))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| AIError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(AIError::ApiError(format!(
+                "获取模型列表失败: HTTP {}", response.status()
+            )));
+        }
+
+        let tags: OllamaTagsResponse = response.json().await
+            .map_err(|e| AIError::ParseError(format!("解析 /api/tags 响应失败: {}", e)))?;
+
+        Ok(tags.models)
+    }
+
+    /// 确保配置的模型已就绪
+    ///
+    /// 先查询 `/api/tags`；模型已安装则直接通过。缺失时按
+    /// `ollama.auto_pull_model` 决定行为：开启则触发 `/api/pull` 自动
+    /// 下载，否则返回列出已安装模型的明确错误，避免真正分析时才
+    /// 发现模型不存在
+    async fn ensure_model_ready(&self) -> Result<(), AIError> {
+        let models = self.list_models().await?;
+        let installed = models.iter()
+            .any(|m| m.name == self.config.model || m.model == self.config.model);
+
+        if installed {
+            return Ok(());
+        }
+
+        if !self.config.auto_pull_model {
+            let available = models.iter().map(|m| m.name.as_str()).collect::<Vec<_>>().join(", ");
+            return Err(AIError::ConfigError(format!(
+                "模型 {} 未安装，已安装的模型: [{}]；可设置 ai.ollama.auto_pull_model = true 自动拉取",
+                self.config.model, available
+            )));
+        }
+
+        info!("模型 {} 未安装，开始自动拉取...", self.config.model);
+        self.pull_model().await
+    }
+
+    /// `POST /api/pull` 并流式打印拉取进度，直到收到 `{"status":"success"}`
+    async fn pull_model(&self) -> Result<(), AIError> {
+        use futures::stream::StreamExt;
+
+        let response = self.client
+            .post(format!("{}/api/pull", self.config.api_endpoint))
+            .json(&serde_json::json!({ "name": self.config.model }))
+            .send()
+            .await
+            .map_err(|e| AIError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(AIError::ApiError(format!(
+                "拉取模型失败: HTTP {}", response.status()
+            )));
+        }
+
+        let mut stream = response.bytes_stream();
+        let mut line_buf = String::new();
+        let mut succeeded = false;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| AIError::NetworkError(e.to_string()))?;
+            line_buf.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(newline_pos) = line_buf.find('\n') {
+                let line = line_buf[..newline_pos].trim().to_string();
+                line_buf.drain(..=newline_pos);
+
+                if line.is_empty() {
+                    continue;
+                }
+
+                let progress: OllamaPullProgress = serde_json::from_str(&line)
+                    .map_err(|e| AIError::ParseError(format!("解析拉取进度失败: {}", e)))?;
+
+                match (progress.completed, progress.total) {
+                    (Some(completed), Some(total)) if total > 0 => {
+                        info!("拉取 {}: {} ({}/{})", self.config.model, progress.status, completed, total);
+                    }
+                    _ => info!("拉取 {}: {}", self.config.model, progress.status),
+                }
+
+                if progress.status == "success" {
+                    succeeded = true;
+                }
+            }
+        }
+
+        if succeeded {
+            info!("✓ 模型 {} 拉取完成", self.config.model);
+            Ok(())
+        } else {
+            Err(AIError::ApiError(format!("拉取模型 {} 未收到 success 状态", self.config.model)))
+        }
+    }
 }
 
 /// Ollama API 响应（字段由反序列化使用）
@@ -183,7 +427,13 @@ struct OllamaResponse {
     context: Option<Vec<u32>>,
 }
 
-/// Ollama 模型信息（字段由反序列化使用）
+/// `GET /api/tags` 响应
+#[derive(Deserialize)]
+struct OllamaTagsResponse {
+    models: Vec<OllamaModelInfo>,
+}
+
+/// `/api/tags` 中的单个模型条目（`size`/`digest` 暂未使用，仅保留字段）
 #[derive(Deserialize)]
 #[allow(dead_code)]
 struct OllamaModelInfo {
@@ -192,3 +442,13 @@ struct OllamaModelInfo {
     size: u64,
     digest: String,
 }
+
+/// `POST /api/pull` 流式返回的单行进度
+#[derive(Deserialize)]
+struct OllamaPullProgress {
+    status: String,
+    #[serde(default)]
+    completed: Option<u64>,
+    #[serde(default)]
+    total: Option<u64>,
+}
@@ -0,0 +1,119 @@
+//! Token 用量与成本/速率统计
+//!
+//! 每个 AI 服务持有一份 `UsageMetrics`，在每次请求后记录消耗的
+//! token 数、是否成功、是否经过内部重试，以及本次请求的耗时，供运维
+//! 侧估算成本、发现异常的错误率/重试率，或请求速率与延迟的异常抬升。
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 某个提供商按输入/输出 token 的计价（单位：美元 / 1K tokens）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProviderPricing {
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    retries: AtomicU64,
+    prompt_tokens: AtomicU64,
+    completion_tokens: AtomicU64,
+    /// 所有已完成请求（成功+失败）的耗时总和（毫秒），配合 `requests`
+    /// 算出自启动以来的累计平均延迟；不按时间窗口滚动，早期请求与
+    /// 近期请求权重相同
+    total_latency_ms: AtomicU64,
+}
+
+/// 某个 AI 服务实例的用量统计句柄，内部用原子计数器保证跨任务并发安全
+#[derive(Clone)]
+pub struct UsageMetrics {
+    provider: String,
+    pricing: ProviderPricing,
+    counters: Arc<Counters>,
+    started_at: Instant,
+}
+
+impl UsageMetrics {
+    pub fn new(provider: impl Into<String>, pricing: ProviderPricing) -> Self {
+        Self {
+            provider: provider.into(),
+            pricing,
+            counters: Arc::new(Counters::default()),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// 记录一次成功请求消耗的 token 数和耗时
+    pub fn record_success(&self, prompt_tokens: u32, completion_tokens: u32, latency: Duration) {
+        self.counters.requests.fetch_add(1, Ordering::Relaxed);
+        self.counters.prompt_tokens.fetch_add(prompt_tokens as u64, Ordering::Relaxed);
+        self.counters.completion_tokens.fetch_add(completion_tokens as u64, Ordering::Relaxed);
+        self.counters.total_latency_ms.fetch_add(latency.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// 记录一次失败请求（不产生 token 消耗，但计入请求、错误计数与耗时）
+    pub fn record_error(&self, latency: Duration) {
+        self.counters.requests.fetch_add(1, Ordering::Relaxed);
+        self.counters.errors.fetch_add(1, Ordering::Relaxed);
+        self.counters.total_latency_ms.fetch_add(latency.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// 记录一次 `max_retries` 内部重试循环触发的重试（`attempt > 0` 时调用一次），
+    /// 与最终是否成功无关——用于发现某个提供商的重试率正在悄悄飙升
+    pub fn record_retry(&self) {
+        self.counters.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 生成当前时刻的用量快照
+    pub fn snapshot(&self) -> UsageSnapshot {
+        let requests = self.counters.requests.load(Ordering::Relaxed);
+        let errors = self.counters.errors.load(Ordering::Relaxed);
+        let retries = self.counters.retries.load(Ordering::Relaxed);
+        let prompt_tokens = self.counters.prompt_tokens.load(Ordering::Relaxed);
+        let completion_tokens = self.counters.completion_tokens.load(Ordering::Relaxed);
+        let total_latency_ms = self.counters.total_latency_ms.load(Ordering::Relaxed);
+
+        let estimated_cost_usd = (prompt_tokens as f64 / 1000.0) * self.pricing.input_per_1k
+            + (completion_tokens as f64 / 1000.0) * self.pricing.output_per_1k;
+
+        let elapsed_minutes = (self.started_at.elapsed().as_secs_f64() / 60.0).max(1.0 / 60.0);
+        let requests_per_minute = requests as f64 / elapsed_minutes;
+
+        let avg_latency_ms = if requests > 0 {
+            total_latency_ms as f64 / requests as f64
+        } else {
+            0.0
+        };
+
+        UsageSnapshot {
+            provider: self.provider.clone(),
+            requests,
+            errors,
+            retries,
+            prompt_tokens,
+            completion_tokens,
+            estimated_cost_usd,
+            requests_per_minute,
+            avg_latency_ms,
+        }
+    }
+}
+
+/// 某个提供商在某一时刻的用量/成本/速率快照，可直接序列化为 JSON
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageSnapshot {
+    pub provider: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub retries: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub estimated_cost_usd: f64,
+    pub requests_per_minute: f64,
+    pub avg_latency_ms: f64,
+}
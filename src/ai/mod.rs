@@ -1,6 +1,10 @@
 pub mod local;
 pub mod kimi;
 pub mod openai;
+pub mod ensemble;
+pub mod extractors;
+pub mod fallback;
+pub mod metrics;
 pub mod models;
 
 use async_trait::async_trait;
@@ -43,6 +47,31 @@ pub trait AIService: Send + Sync {
 
     /// 获取提供商类型
     fn provider(&self) -> AIProvider;
+
+    /// 获取该服务的 token 用量/成本/速率快照
+    ///
+    /// 默认返回 `None`；Ollama/Kimi/OpenAI 各自覆盖并返回自己的快照，
+    /// 组合服务（ensemble、fallback）不覆盖，返回 `None`。
+    fn usage_snapshot(&self) -> Option<metrics::UsageSnapshot> {
+        None
+    }
+
+    /// 增量获取模型输出，每收到一段内容就回调一次 `on_chunk`，最终返回
+    /// 与 `analyze` 相同的结构化结果
+    ///
+    /// 默认实现退化为一次性调用 `analyze`，把完整原始响应当作唯一一次
+    /// 回调；只有真正支持流式接口的提供商（如 Ollama）才需要覆盖它。
+    async fn analyze_streaming(
+        &self,
+        message: &str,
+        mut on_chunk: Box<dyn FnMut(&str) + Send>,
+    ) -> Result<AnalysisResult, AIError> {
+        let result = self.analyze(message).await?;
+        if let Some(raw) = &result.raw_response {
+            on_chunk(raw);
+        }
+        Ok(result)
+    }
 }
 
 /// AI 服务工厂，根据配置创建对应的服务实例
@@ -51,7 +80,20 @@ pub struct AIServiceFactory;
 impl AIServiceFactory {
     /// 根据配置创建 AI 服务
     pub fn create(config: &AIConfig) -> Result<Box<dyn AIService>, AIError> {
-        let provider = config.provider.to_lowercase();
+        let provider_chain = config.provider_chain();
+
+        if provider_chain.is_empty() {
+            error!("ai.provider 未配置任何提供商");
+            return Err(AIError::ConfigError("ai.provider 不能为空".to_string()));
+        }
+
+        if provider_chain.len() > 1 {
+            info!("初始化故障转移链: {}", provider_chain.join(" -> "));
+            let service = fallback::FallbackAIService::new(config, &provider_chain)?;
+            return Ok(Box::new(service));
+        }
+
+        let provider = provider_chain[0].clone();
 
         match provider.as_str() {
             "ollama" | "local" => {
@@ -69,6 +111,11 @@ impl AIServiceFactory {
                 let service = openai::OpenAIService::new(config)?;
                 Ok(Box::new(service))
             }
+            "ensemble" => {
+                info!("初始化 Ensemble 多提供商共识服务...");
+                let service = ensemble::EnsembleService::new(config)?;
+                Ok(Box::new(service))
+            }
             _ => {
                 error!("不支持的 AI 提供商: {}", provider);
                 Err(AIError::UnsupportedProvider(provider))
@@ -82,20 +129,27 @@ use serde_json::Value;
 
 /// 通用响应解析函数
 pub fn parse_analysis_response(content: &str, original_message: &str, source: &str) -> Result<AnalysisResult, AIError> {
-    // 首先尝试解析 JSON 格式的响应
-    if let Ok(json_data) = serde_json::from_str::<Value>(content) {
+    // 首先尝试解析 JSON 格式的响应；模型经常把 JSON 包在 Markdown 代码
+    // 围栏里、前后夹杂说明文字，或嵌套在非标准的包装对象里，直接解析
+    // 失败时 extract_json_value 会做一次恢复尝试
+    if let Some(json_data) = extract_json_value(content) {
         debug!("成功解析 JSON 响应，来源: {}", source);
 
-        // 如果 JSON 包含完整的分析结果
-        if let Some(is_relevant) = json_data["is_relevant"].as_bool() {
+        // 如果 JSON 包含完整的分析结果；字段可能不在顶层，递归查找
+        if let Some(is_relevant) = nested_bool(&json_data, "is_relevant") {
+            let contract_address = nested_str(&json_data, "contract_address");
+            let chain = nested_str(&json_data, "chain")
+                .or_else(|| contract_address.as_deref().and_then(extractors::detect_chain_for_address).map(String::from));
+
             return Ok(AnalysisResult {
                 is_relevant,
-                token_name: json_data["token_name"].as_str().map(String::from),
-                contract_address: json_data["contract_address"].as_str().map(String::from),
-                recommendation: json_data["recommendation"].as_str().map(String::from),
-                reason: json_data["reason"].as_str().map(String::from),
-                confidence: json_data["confidence"].as_f64().unwrap_or(0.0) as f32,
-                urgency: json_data["urgency"].as_i64().unwrap_or(0) as i32,
+                token_name: nested_str(&json_data, "token_name"),
+                contract_address,
+                chain,
+                recommendation: nested_str(&json_data, "recommendation"),
+                reason: nested_str(&json_data, "reason"),
+                confidence: nested_f64(&json_data, "confidence").unwrap_or(0.0) as f32,
+                urgency: nested_i64(&json_data, "urgency").unwrap_or(0) as i32,
                 source: source.to_string(),
                 timestamp: chrono::Utc::now().timestamp(),
                 raw_response: Some(content.to_string()),
@@ -106,12 +160,15 @@ pub fn parse_analysis_response(content: &str, original_message: &str, source: &s
         if is_token_related_message(original_message) {
             info!("消息内容与 Token 相关，但响应格式不标准，手动解析");
 
+            let extracted = extractors::run_pipeline(original_message);
+
             return Ok(AnalysisResult {
                 is_relevant: true,
-                token_name: extract_token_name(original_message),
-                contract_address: extract_contract_address(original_message),
+                token_name: extracted.token_name,
+                contract_address: extracted.contract_address,
+                chain: extracted.chain,
                 recommendation: extract_recommendation(&json_data, content),
-                reason: json_data["reason"].as_str().or(Some(content)).map(String::from),
+                reason: nested_str(&json_data, "reason").or_else(|| Some(content.to_string())),
                 confidence: 0.6,
                 urgency: 5,
                 source: source.to_string(),
@@ -125,11 +182,13 @@ pub fn parse_analysis_response(content: &str, original_message: &str, source: &s
     info!("响应不是标准 JSON，使用启发式分析");
 
     let is_relevant = is_token_related_message(original_message);
+    let extracted = extractors::run_pipeline(original_message);
 
     Ok(AnalysisResult {
         is_relevant,
-        token_name: extract_token_name(original_message),
-        contract_address: extract_contract_address(original_message),
+        token_name: extracted.token_name,
+        contract_address: extracted.contract_address,
+        chain: extracted.chain,
         recommendation: if is_relevant { extract_recommendation_from_text(content) } else { None },
         reason: if is_relevant { Some(content.to_string()) } else { None },
         confidence: if is_relevant { 0.5 } else { 0.0 },
@@ -153,40 +212,220 @@ fn is_token_related_message(message: &str) -> bool {
     keywords.iter().any(|&kw| lower_msg.contains(kw))
 }
 
-/// 提取 Token 名称
-fn extract_token_name(message: &str) -> Option<String> {
-    // 简单的启发式：查找大写的单词（可能是 Token 名称）
-    let words: Vec<&str> = message.split_whitespace().collect();
-    for word in words {
-        if word.len() >= 2 && word.len() <= 10 && word.chars().all(|c| c.is_uppercase() || c.is_ascii_digit()) {
-            return Some(word.to_string());
+/// 从 JSON 数据中提取交易建议
+fn extract_recommendation(json_data: &Value, _raw_content: &str) -> Option<String> {
+    // 首先从标准字段提取（可能嵌套在非顶层）
+    if let Some(rec) = nested_str(json_data, "recommendation") {
+        return Some(rec);
+    }
+
+    // 从内容中通过关键词提取
+    let content = nested_str(json_data, "content")
+        .or_else(|| nested_str(json_data, "response"))?;
+
+    extract_recommendation_from_text(&content)
+}
+
+/// 在直接解析失败时，从内容中恢复出嵌入的 JSON 对象
+///
+/// 依次尝试：原样解析 → 剥离 Markdown 代码围栏后解析 → 在原始内容中
+/// 直接定位第一个括号深度平衡的 `{...}` 块再解析，覆盖模型把 JSON
+/// 包在围栏里、或 JSON 前后夹杂说明文字的常见输出方式
+fn extract_json_value(content: &str) -> Option<Value> {
+    if let Ok(value) = serde_json::from_str::<Value>(content) {
+        return Some(value);
+    }
+
+    let stripped = strip_code_fences(content);
+    if let Some(block) = find_balanced_json_block(&stripped) {
+        if let Ok(value) = serde_json::from_str::<Value>(&block) {
+            return Some(value);
         }
     }
-    None
+
+    let block = find_balanced_json_block(content)?;
+    serde_json::from_str::<Value>(&block).ok()
 }
 
-/// 提取合约地址（简单的 ETH/BSC 地址格式匹配）
-fn extract_contract_address(message: &str) -> Option<String> {
-    use regex::Regex;
+/// 剥离 Markdown 代码围栏（` ```json ... ``` ` 或 ` ``` ... ``` `）；
+/// 内容不是以围栏开头时原样返回
+fn strip_code_fences(content: &str) -> String {
+    let trimmed = content.trim();
+    if !trimmed.starts_with("```") {
+        return content.to_string();
+    }
 
-    // 0x 开头的 42 位地址
-    let re = Regex::new(r"0x[a-fA-F0-9]{40}").ok()?;
-    re.find(message).map(|m| m.as_str().to_string())
+    let after_open = trimmed
+        .trim_start_matches("```")
+        .trim_start_matches("json")
+        .trim_start_matches("JSON");
+
+    match after_open.rfind("```") {
+        Some(end) => after_open[..end].to_string(),
+        None => after_open.to_string(),
+    }
 }
 
-/// 从 JSON 数据中提取交易建议
-fn extract_recommendation(json_data: &Value, _raw_content: &str) -> Option<String> {
-    // 首先从标准字段提取
-    if let Some(rec) = json_data["recommendation"].as_str() {
-        return Some(rec.to_string());
+/// 在文本中查找第一个括号深度平衡的 `{...}` 块，通过逐字符状态机
+/// 正确跳过字符串字面量内的花括号与转义字符
+fn find_balanced_json_block(content: &str) -> Option<String> {
+    let chars: Vec<char> = content.chars().collect();
+    let start = chars.iter().position(|&c| c == '{')?;
+
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, &c) in chars.iter().enumerate().skip(start) {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(chars[start..=i].iter().collect());
+                }
+            }
+            _ => {}
+        }
     }
 
-    // 从内容中通过关键词提取
-    let content = json_data["content"]
-        .as_str()
-        .or_else(|| json_data["response"].as_str())?;
+    None
+}
+
+/// 在一个（可能嵌套的）`Value` 中深度优先查找第一个名为 `key` 的字段，
+/// 不要求其出现在顶层
+fn find_nested_field<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => {
+            if let Some(found) = map.get(key) {
+                return Some(found);
+            }
+            map.values().find_map(|v| find_nested_field(v, key))
+        }
+        Value::Array(items) => items.iter().find_map(|v| find_nested_field(v, key)),
+        _ => None,
+    }
+}
+
+fn nested_bool(value: &Value, key: &str) -> Option<bool> {
+    find_nested_field(value, key).and_then(Value::as_bool)
+}
+
+fn nested_str(value: &Value, key: &str) -> Option<String> {
+    find_nested_field(value, key).and_then(Value::as_str).map(String::from)
+}
 
-    extract_recommendation_from_text(content)
+fn nested_f64(value: &Value, key: &str) -> Option<f64> {
+    find_nested_field(value, key).and_then(Value::as_f64)
+}
+
+fn nested_i64(value: &Value, key: &str) -> Option<i64> {
+    find_nested_field(value, key).and_then(Value::as_i64)
+}
+
+/// `report_token_analysis` 函数调用的 JSON-schema 声明
+///
+/// 镜像 `AnalysisResult` 的字段，供支持 function-calling 的提供商
+/// （Kimi、OpenAI 等）在请求体的 `tools` 数组中使用，并配合
+/// `tool_choice` 强制模型通过该函数返回结构化结果，而不是在文本里
+/// 自由发挥 JSON。
+pub fn report_token_analysis_tool() -> Value {
+    serde_json::json!({
+        "type": "function",
+        "function": {
+            "name": "report_token_analysis",
+            "description": "上报对 Telegram 消息的 meme token 交易分析结果",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "is_relevant": {
+                        "type": "boolean",
+                        "description": "是否与 Token 交易相关"
+                    },
+                    "token_name": {
+                        "type": "string",
+                        "description": "Token 名称（如果有）"
+                    },
+                    "contract_address": {
+                        "type": "string",
+                        "description": "合约地址（ETH/BSC 格式：0x...）"
+                    },
+                    "recommendation": {
+                        "type": "string",
+                        "enum": ["买入", "卖出", "持有"],
+                        "description": "交易建议"
+                    },
+                    "reason": {
+                        "type": "string",
+                        "description": "详细的推荐理由"
+                    },
+                    "confidence": {
+                        "type": "number",
+                        "minimum": 0.0,
+                        "maximum": 1.0,
+                        "description": "置信度"
+                    },
+                    "urgency": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": 10,
+                        "description": "紧急程度（1=不紧急，10=非常紧急）"
+                    }
+                },
+                "required": ["is_relevant"]
+            }
+        }
+    })
+}
+
+/// `report_token_analysis` 工具调用参数
+#[derive(serde::Deserialize)]
+struct ReportTokenAnalysisArgs {
+    is_relevant: bool,
+    token_name: Option<String>,
+    contract_address: Option<String>,
+    recommendation: Option<String>,
+    reason: Option<String>,
+    confidence: Option<f32>,
+    urgency: Option<i32>,
+}
+
+/// 将模型通过 `tool_calls` 返回的函数参数（JSON 字符串）反序列化为 `AnalysisResult`
+///
+/// 这条路径绕开了 `parse_analysis_response` 的文本启发式解析：模型被
+/// `tool_choice` 强制调用 `report_token_analysis`，参数已经是结构化
+/// JSON，不需要再从自然语言里猜测字段。
+pub fn analysis_result_from_tool_call(arguments: &str, source: &str) -> Result<AnalysisResult, AIError> {
+    let args: ReportTokenAnalysisArgs = serde_json::from_str(arguments)
+        .map_err(|e| AIError::ParseError(format!("解析 tool_calls 参数失败: {}", e)))?;
+
+    let chain = args.contract_address.as_deref().and_then(extractors::detect_chain_for_address).map(String::from);
+
+    Ok(AnalysisResult {
+        is_relevant: args.is_relevant,
+        token_name: args.token_name,
+        contract_address: args.contract_address,
+        chain,
+        recommendation: args.recommendation,
+        reason: args.reason,
+        confidence: args.confidence.unwrap_or(0.0),
+        urgency: args.urgency.unwrap_or(0),
+        source: source.to_string(),
+        timestamp: chrono::Utc::now().timestamp(),
+        raw_response: Some(arguments.to_string()),
+    })
 }
 
 /// 从文本中提取交易建议
@@ -203,3 +442,43 @@ fn extract_recommendation_from_text(content: &str) -> Option<String> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_json_value_plain() {
+        let content = r#"{"is_relevant": true, "confidence": 0.9}"#;
+        let value = extract_json_value(content).expect("应解析为 JSON");
+        assert_eq!(nested_bool(&value, "is_relevant"), Some(true));
+    }
+
+    #[test]
+    fn test_extract_json_value_fenced_with_prose() {
+        let content = "这是分析结果：\n```json\n{\"is_relevant\": true, \"token_name\": \"PEPE\"}\n```\n希望有帮助！";
+        let value = extract_json_value(content).expect("应从围栏中恢复 JSON");
+        assert_eq!(nested_str(&value, "token_name"), Some("PEPE".to_string()));
+    }
+
+    #[test]
+    fn test_extract_json_value_respects_string_braces() {
+        let content = r#"前言 {"reason": "包含 } 和 { 的字符串", "is_relevant": false} 结语"#;
+        let value = extract_json_value(content).expect("应正确跳过字符串内的花括号");
+        assert_eq!(nested_bool(&value, "is_relevant"), Some(false));
+    }
+
+    #[test]
+    fn test_extract_json_value_no_json_returns_none() {
+        assert!(extract_json_value("这只是一段普通文本，没有 JSON").is_none());
+    }
+
+    #[test]
+    fn test_find_nested_field_walks_wrapper_objects() {
+        let value: Value = serde_json::from_str(
+            r#"{"result": {"analysis": {"is_relevant": true, "confidence": 0.75}}}"#,
+        ).unwrap();
+        assert_eq!(nested_bool(&value, "is_relevant"), Some(true));
+        assert_eq!(nested_f64(&value, "confidence"), Some(0.75));
+    }
+}
@@ -13,6 +13,11 @@ pub struct AnalysisResult {
     /// 合约地址
     pub contract_address: Option<String>,
 
+    /// 合约地址所属链: "evm"、"solana"、"tron"，未识别出地址或无法判断
+    /// 所属链时为 `None`
+    #[serde(default)]
+    pub chain: Option<String>,
+
     /// 交易建议: 买入/卖出/持有
     pub recommendation: Option<String>,
 
@@ -43,6 +48,7 @@ impl AnalysisResult {
             is_relevant: false,
             token_name: None,
             contract_address: None,
+            chain: None,
             recommendation: None,
             reason: None,
             confidence: 0.0,
@@ -81,9 +87,12 @@ impl AnalysisResult {
             summary.push_str(&format!("> **Token**: {}\n", token_name));
         }
 
-        // 合约地址
+        // 合约地址（带所属链，未识别出链时省略）
         if let Some(contract) = &self.contract_address {
-            summary.push_str(&format!("> **合约**: `{}`\n", contract));
+            match &self.chain {
+                Some(chain) => summary.push_str(&format!("> **合约** ({}): `{}`\n", chain, contract)),
+                None => summary.push_str(&format!("> **合约**: `{}`\n", contract)),
+            }
         }
 
         // 交易建议
@@ -115,6 +124,8 @@ pub enum AIProvider {
     Ollama,
     Kimi,
     OpenAI,
+    Ensemble,
+    Fallback,
 }
 
 impl fmt::Display for AIProvider {
@@ -123,6 +134,8 @@ impl fmt::Display for AIProvider {
             AIProvider::Ollama => write!(f, "ollama"),
             AIProvider::Kimi => write!(f, "kimi"),
             AIProvider::OpenAI => write!(f, "openai"),
+            AIProvider::Ensemble => write!(f, "ensemble"),
+            AIProvider::Fallback => write!(f, "fallback"),
         }
     }
 }
@@ -133,6 +146,7 @@ impl From<&str> for AIProvider {
             "ollama" | "local" => AIProvider::Ollama,
             "kimi" => AIProvider::Kimi,
             "openai" => AIProvider::OpenAI,
+            "ensemble" => AIProvider::Ensemble,
             _ => AIProvider::Kimi, // 默认
         }
     }
@@ -159,8 +173,12 @@ pub struct Message {
     /// 发送者（如果有）
     pub sender: Option<String>,
 
-    /// 媒体类型（如果有）
+    /// 媒体类型（如果有），例如 "photo"、"document"
     pub media_type: Option<String>,
+
+    /// 媒体原始字节（如果有），由 HTTP 入口解码 base64 负载得到；
+    /// MTProto 入口目前只打媒体类型标签，不下载原始字节，此字段为 `None`
+    pub media_data: Option<Vec<u8>>,
 }
 
 impl Message {
@@ -174,6 +192,7 @@ impl Message {
             timestamp,
             sender: None,
             media_type: None,
+            media_data: None,
         }
     }
 
@@ -182,12 +201,20 @@ impl Message {
         self.media_type.is_some()
     }
 
+    /// 是否携带可以用 `sendPhoto` 转发的图片数据
+    pub fn has_image_data(&self) -> bool {
+        self.media_type.as_deref() == Some("photo") && self.media_data.is_some()
+    }
+
     /// 消息摘要（用于日志）
     pub fn summary(&self) -> String {
-        let preview = if self.text.len() > 50 {
-            format!("{}...", &self.text[..50])
+        // UTF-8安全的字符截断
+        let mut chars = self.text.chars();
+        let preview: String = chars.by_ref().take(50).collect();
+        let preview = if chars.next().is_some() {
+            format!("{}...", preview)
         } else {
-            self.text.clone()
+            preview
         };
 
         format!("[{}] {}: {}", self.channel_name, self.id, preview)
@@ -315,16 +342,32 @@ pub struct SummaryReport {
 
     /// 相关消息数
     pub relevant_messages: usize,
+
+    /// 当前 AI 服务的 token 用量/成本/重试率/平均延迟快照；不产生用量
+    /// 统计的服务（如 ensemble/fallback 组合服务）或调用方未提供时为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_usage: Option<crate::ai::metrics::UsageSnapshot>,
 }
 
 impl SummaryReport {
     /// 创建汇总报告
     pub fn new(tokens: Vec<TokenInfo>, total_messages: usize, relevant_messages: usize) -> Self {
+        Self::with_ai_usage(tokens, total_messages, relevant_messages, None)
+    }
+
+    /// 创建汇总报告，附带调用方已取得的 AI 用量快照
+    pub fn with_ai_usage(
+        tokens: Vec<TokenInfo>,
+        total_messages: usize,
+        relevant_messages: usize,
+        ai_usage: Option<crate::ai::metrics::UsageSnapshot>,
+    ) -> Self {
         Self {
             tokens,
             generated_at: chrono::Utc::now().timestamp(),
             total_messages,
             relevant_messages,
+            ai_usage,
         }
     }
 
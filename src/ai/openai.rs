@@ -0,0 +1,244 @@
+use super::{AIService, AIError};
+use super::metrics::{ProviderPricing, UsageMetrics, UsageSnapshot};
+use super::models::{AnalysisResult, AIProvider};
+use async_trait::async_trait;
+use config::{AIConfig, OpenAIConfig};
+use reqwest::Client;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info};
+
+/// OpenAI（及兼容接口，如 DeepSeek）服务实现
+pub struct OpenAIService {
+    client: Client,
+    config: OpenAIConfig,
+    timeout: Duration,
+    prompt_template: String,
+    max_retries: u32,
+    metrics: UsageMetrics,
+}
+
+impl OpenAIService {
+    /// 创建 OpenAI 服务实例
+    pub fn new(ai_config: &AIConfig) -> Result<Self, AIError> {
+        let openai_config = ai_config.openai.as_ref()
+            .ok_or_else(|| AIError::ConfigError("OpenAI 配置未找到".to_string()))?;
+
+        let mut client_builder = Client::builder()
+            .timeout(Duration::from_secs(ai_config.timeout_seconds));
+
+        if let Some(proxy_url) = &ai_config.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| AIError::ConfigError(format!("ai.proxy 无效: {}", e)))?;
+            client_builder = client_builder.proxy(proxy);
+            info!("OpenAI 客户端已启用代理: {}", proxy_url);
+        }
+
+        let client = client_builder
+            .build()
+            .map_err(|e| AIError::NetworkError(e.to_string()))?;
+
+        let pricing = ProviderPricing {
+            input_per_1k: openai_config.input_price_per_1k,
+            output_per_1k: openai_config.output_price_per_1k,
+        };
+
+        Ok(Self {
+            client,
+            config: openai_config.clone(),
+            timeout: Duration::from_secs(ai_config.timeout_seconds),
+            prompt_template: ai_config.prompt_template.clone(),
+            max_retries: ai_config.max_retries,
+            metrics: UsageMetrics::new("openai", pricing),
+        })
+    }
+
+    /// 构建系统提示词
+    fn build_system_prompt(&self) -> String {
+        r#"你是一名专业的加密货币交易信息分析师。
+
+你的任务是分析 Telegram 消息，判断是否在讨论 meme token 交易信息。
+
+如果是相关消息，请提取以下信息并以 JSON 格式返回：
+{
+  "is_relevant": true,
+  "token_name": "Token名称（如果有）",
+  "contract_address": "合约地址（ETH/BSC格式：0x...）",
+  "recommendation": "买入/卖出/持有",
+  "reason": "详细的推荐理由",
+  "confidence": 0.85,
+  "urgency": 7
+}
+
+如果不是相关消息，返回：
+{"is_relevant": false}
+
+注意：
+- confidence 是 0.0 到 1.0 之间的浮点数
+- urgency 是 1 到 10 之间的整数（1=不紧急，10=非常紧急）
+- 只返回 JSON，不要包含其他文本
+"#.to_string()
+    }
+
+    /// 构建完整的提示词
+    fn build_prompt(&self, message: &str) -> String {
+        self.prompt_template.replace("{}", message)
+    }
+
+    /// 解析 JSON 响应
+    fn parse_response(&self, content: &str, original_message: &str) -> Result<AnalysisResult, AIError> {
+        use super::parse_analysis_response;
+        parse_analysis_response(content, original_message, "openai")
+            .map_err(|e| AIError::ParseError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl AIService for OpenAIService {
+    async fn analyze(&self, message: &str) -> Result<AnalysisResult, AIError> {
+        // UTF-8安全的字符截断
+        let preview: String = message.chars().take(50).collect();
+        debug!("使用 OpenAI 分析消息: {}", preview);
+
+        // 构建请求体：通过 tools + tool_choice 强制模型调用
+        // report_token_analysis，而不是在文本里自由返回 JSON
+        let tool = super::report_token_analysis_tool();
+        let request_body = serde_json::json!({
+            "model": self.config.model,
+            "messages": [
+                {
+                    "role": "system",
+                    "content": self.build_system_prompt()
+                },
+                {
+                    "role": "user",
+                    "content": self.build_prompt(message)
+                }
+            ],
+            "temperature": 0.3,
+            "max_tokens": 500,
+            "stream": false,
+            "tools": [tool],
+            "tool_choice": {"type": "function", "function": {"name": "report_token_analysis"}}
+        });
+
+        debug!("发送请求到 OpenAI API...");
+
+        // 发送请求并处理重试
+        let started = Instant::now();
+        let mut last_error = None;
+        for attempt in 0..=self.max_retries {
+            if attempt > 0 {
+                info!("第 {} 次重试...", attempt);
+                self.metrics.record_retry();
+                tokio::time::sleep(Duration::from_secs(2_u64.pow(attempt - 1))).await;
+            }
+
+            match self.client
+                .post(format!("{}/chat/completions", self.config.base_url))
+                .header("Authorization", format!("Bearer {}", self.config.api_key))
+                .header("Content-Type", "application/json")
+                .json(&request_body)
+                .timeout(self.timeout)
+                .send()
+                .await
+            {
+                Ok(response) => {
+                    let status = response.status();
+
+                    if status.is_success() {
+                        match response.json::<Value>().await {
+                            Ok(result) => {
+                                debug!("成功收到 OpenAI API 响应");
+
+                                let prompt_tokens = result["usage"]["prompt_tokens"].as_u64().unwrap_or(0) as u32;
+                                let completion_tokens = result["usage"]["completion_tokens"].as_u64().unwrap_or(0) as u32;
+                                self.metrics.record_success(prompt_tokens, completion_tokens, started.elapsed());
+
+                                // 优先走 tool_calls 结构化路径；旧模型/未命中
+                                // tool_choice 时回退到文本解析
+                                if let Some(arguments) = result["choices"][0]["message"]["tool_calls"][0]["function"]["arguments"].as_str() {
+                                    debug!("OpenAI 返回 tool_calls，使用结构化解析");
+                                    return super::analysis_result_from_tool_call(arguments, "openai")
+                                        .map_err(|e| AIError::ParseError(e.to_string()));
+                                }
+
+                                let content = result["choices"][0]["message"]["content"]
+                                    .as_str()
+                                    .ok_or_else(|| AIError::ParseError("响应中没有 content 字段".to_string()))?;
+
+                                debug!("OpenAI 未返回 tool_calls，回退到文本解析");
+                                return self.parse_response(content, message);
+                            }
+                            Err(e) => {
+                                error!("解析 OpenAI API 响应失败: {}", e);
+                                last_error = Some(AIError::ParseError(e.to_string()));
+                            }
+                        }
+                    } else {
+                        let error_text = response.text().await
+                            .unwrap_or_else(|_| "无法读取错误信息".to_string());
+                        error!("OpenAI API 返回错误状态 {}: {}", status, error_text);
+                        last_error = Some(AIError::ApiError(format!("HTTP {}: {}", status, error_text)));
+                    }
+                }
+                Err(e) => {
+                    error!("请求 OpenAI API 失败: {}", e);
+                    last_error = Some(AIError::NetworkError(e.to_string()));
+                }
+            }
+        }
+
+        self.metrics.record_error(started.elapsed());
+        Err(last_error.unwrap_or_else(|| AIError::ApiError("所有重试均失败".to_string())))
+    }
+
+    async fn health_check(&self) -> bool {
+        debug!("检查 OpenAI API 健康状态...");
+
+        let request_body = serde_json::json!({
+            "model": self.config.model,
+            "messages": [{
+                "role": "user",
+                "content": "Hi"
+            }],
+            "max_tokens": 1,
+            "stream": false
+        });
+
+        match self.client
+            .post(format!("{}/chat/completions", self.config.base_url))
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .json(&request_body)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+        {
+            Ok(response) => {
+                let is_success = response.status().is_success();
+                if is_success {
+                    info!("✓ OpenAI API 健康检查通过");
+                } else {
+                    error!("✗ OpenAI API 健康检查失败: HTTP {}", response.status());
+                }
+                is_success
+            }
+            Err(e) => {
+                error!("✗ OpenAI API 健康检查失败: {}", e);
+                false
+            }
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("OpenAI API Service ({})", self.config.model)
+    }
+
+    fn provider(&self) -> AIProvider {
+        AIProvider::OpenAI
+    }
+
+    fn usage_snapshot(&self) -> Option<UsageSnapshot> {
+        Some(self.metrics.snapshot())
+    }
+}
@@ -0,0 +1,107 @@
+//! 监控频道列表的 JSON 文件持久化
+//!
+//! `MessageProcessor` 里的频道集合原本只活在内存里，进程一重启就清空。
+//! [`ChannelStore`] 把同一份 `ChannelInfo` 列表落盘到一个 JSON 文件，
+//! 启动时读回、每次 `add_channel`/`remove_channel`/`update_channels`
+//! 之后整份重写——频道数量级很小（几十到几百），没必要上 SQLite 表。
+
+use crate::http::channel_handler::ChannelInfo;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// 频道列表持久化句柄
+#[derive(Debug, Clone)]
+pub struct ChannelStore {
+    path: PathBuf,
+}
+
+impl ChannelStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// 读取已持久化的频道列表；文件不存在时视为“尚无历史数据”，返回空列表
+    /// 而不是报错，方便首次启动
+    pub fn load(&self) -> Result<Vec<ChannelInfo>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("读取频道列表文件失败: {}", self.path.display()))?;
+
+        if content.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        serde_json::from_str(&content)
+            .with_context(|| format!("解析频道列表文件失败: {}", self.path.display()))
+    }
+
+    /// 原子重写整份频道列表：先写到同目录下的临时文件，再 `rename` 覆盖
+    /// 目标路径，避免进程在写入中途崩溃/被杀时留下截断的文件
+    pub fn save(&self, channels: &[ChannelInfo]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("创建频道列表目录失败: {}", parent.display()))?;
+            }
+        }
+
+        let json = serde_json::to_string_pretty(channels).context("序列化频道列表失败")?;
+        let tmp_path = tmp_path_for(&self.path);
+
+        std::fs::write(&tmp_path, json)
+            .with_context(|| format!("写入临时频道列表文件失败: {}", tmp_path.display()))?;
+
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("替换频道列表文件失败: {}", self.path.display()))?;
+
+        Ok(())
+    }
+}
+
+fn tmp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|n| format!("{}.tmp", n.to_string_lossy()))
+        .unwrap_or_else(|| "channels.json.tmp".to_string());
+
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(file_name),
+        _ => PathBuf::from(file_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_file_returns_empty() {
+        let store = ChannelStore::new("/tmp/tg-monitor-test-does-not-exist.json");
+        assert_eq!(store.load().unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn test_save_then_load_roundtrip() {
+        let path = std::env::temp_dir().join(format!(
+            "tg-monitor-channel-store-test-{}.json",
+            std::process::id()
+        ));
+        let store = ChannelStore::new(&path);
+
+        let channels = vec![ChannelInfo {
+            channel_id: -1001234567890,
+            channel_name: Some("测试频道".to_string()),
+            added_at: 1700000000,
+        }];
+
+        store.save(&channels).unwrap();
+        let loaded = store.load().unwrap();
+
+        assert_eq!(loaded, channels);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
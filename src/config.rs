@@ -1,5 +1,7 @@
 use anyhow::{Context, Result};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::env;
 use std::fs;
 use std::path::Path;
 
@@ -9,6 +11,15 @@ pub struct Config {
     pub telegram: TelegramConfig,
     pub ai: AIConfig,
     pub processing: ProcessingConfig,
+
+    /// 持久化存储配置（可选，未配置时退化为纯内存运行）
+    pub storage: Option<StorageConfig>,
+
+    /// 输出 sink 列表（`[[sinks]]`，可选）；留空时退化为只通过
+    /// `telegram.target_user` 发送一份 DM，与引入 sink 子系统之前的行为
+    /// 一致
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
 }
 
 impl Config {
@@ -22,8 +33,27 @@ impl Config {
 
         let content = fs::read_to_string(path)
             .with_context(|| format!("无法读取配置文件: {}", path.display()))?;
+        let content = expand_env_vars(&content)?;
 
-        let config: Config = toml::from_str(&content)
+        let mut value: toml::Value = toml::from_str(&content)
+            .with_context(|| format!("配置文件格式错误: {}", path.display()))?;
+
+        // 允许在主配置文件旁放一个 secrets.toml（建议加入 .gitignore），
+        // 用它覆盖 bot_token/api_hash/api_key 等敏感字段，这样主配置模板
+        // 本身可以提交到版本库而不泄露凭据
+        let secrets_path = path.with_file_name("secrets.toml");
+        if secrets_path.exists() {
+            let secrets_content = fs::read_to_string(&secrets_path)
+                .with_context(|| format!("无法读取密钥文件: {}", secrets_path.display()))?;
+            let secrets_content = expand_env_vars(&secrets_content)?;
+            let secrets: toml::Value = toml::from_str(&secrets_content)
+                .with_context(|| format!("密钥文件格式错误: {}", secrets_path.display()))?;
+
+            merge_toml(&mut value, secrets);
+        }
+
+        let config: Config = value
+            .try_into()
             .with_context(|| format!("配置文件格式错误: {}", path.display()))?;
 
         // 验证配置
@@ -51,36 +81,83 @@ impl Config {
             anyhow::bail!("telegram.target_user 不能为空或 0");
         }
 
-        // 验证 AI 配置
-        if self.ai.provider.is_empty() {
+        if self.telegram.bot_token.is_empty() {
+            anyhow::bail!("telegram.bot_token 不能为空");
+        }
+
+        // 验证 AI 配置。`ai.provider` 可以是单个提供商，也可以是逗号分隔/
+        // TOML 列表形式的故障转移链（如 "ollama,kimi"），链上每一环都要按
+        // 自己的类型校验对应的 `[ai.xxx]` 配置是否齐全
+        let provider_chain = self.ai.provider_chain();
+        if provider_chain.is_empty() {
             anyhow::bail!("ai.provider 不能为空");
         }
 
-        match self.ai.provider.as_str() {
-            "ollama" | "local" => {
-                if self.ai.ollama.is_none() {
-                    anyhow::bail!("使用 ollama 时，必须配置 [ai.ollama]");
+        for provider in &provider_chain {
+            match provider.as_str() {
+                "ollama" | "local" => {
+                    let ollama = self.ai.ollama.as_ref()
+                        .ok_or_else(|| anyhow::anyhow!("使用 ollama 时，必须配置 [ai.ollama]"))?;
+
+                    if !matches!(ollama.api_mode.as_str(), "chat" | "generate") {
+                        anyhow::bail!(
+                            "ai.ollama.api_mode 不支持的值: {}，支持: chat, generate",
+                            ollama.api_mode
+                        );
+                    }
                 }
-            }
-            "kimi" => {
-                if self.ai.kimi.is_none() {
-                    anyhow::bail!("使用 kimi 时，必须配置 [ai.kimi]");
+                "kimi" => {
+                    if self.ai.kimi.is_none() {
+                        anyhow::bail!("使用 kimi 时，必须配置 [ai.kimi]");
+                    }
+                    let kimi = self.ai.kimi.as_ref().unwrap();
+                    if kimi.api_key.starts_with("sk-") && kimi.api_key.len() < 10 {
+                        anyhow::bail!("ai.kimi.api_key 格式不正确");
+                    }
                 }
-                let kimi = self.ai.kimi.as_ref().unwrap();
-                if kimi.api_key.starts_with("sk-") && kimi.api_key.len() < 10 {
-                    anyhow::bail!("ai.kimi.api_key 格式不正确");
+                "openai" => {
+                    if self.ai.openai.is_none() {
+                        anyhow::bail!("使用 openai 时，必须配置 [ai.openai]");
+                    }
                 }
-            }
-            "openai" => {
-                if self.ai.openai.is_none() {
-                    anyhow::bail!("使用 openai 时，必须配置 [ai.openai]");
+                "ensemble" => {
+                    if provider_chain.len() > 1 {
+                        anyhow::bail!("ai.provider 中的 ensemble 不能与其他提供商组成故障转移链");
+                    }
+
+                    let ensemble = self.ai.ensemble.as_ref()
+                        .ok_or_else(|| anyhow::anyhow!("使用 ensemble 时，必须配置 [ai.ensemble]"))?;
+
+                    if ensemble.providers.len() < 2 {
+                        anyhow::bail!("ai.ensemble.providers 至少需要配置 2 个子提供商");
+                    }
+
+                    if ensemble.quorum == 0 || ensemble.quorum > ensemble.providers.len() {
+                        anyhow::bail!("ai.ensemble.quorum 必须介于 1 到 providers 数量之间");
+                    }
+
+                    for sub_provider in &ensemble.providers {
+                        match sub_provider.as_str() {
+                            "ollama" | "local" if self.ai.ollama.is_none() => {
+                                anyhow::bail!("ensemble 中配置了 ollama，但缺少 [ai.ollama]");
+                            }
+                            "kimi" if self.ai.kimi.is_none() => {
+                                anyhow::bail!("ensemble 中配置了 kimi，但缺少 [ai.kimi]");
+                            }
+                            "openai" if self.ai.openai.is_none() => {
+                                anyhow::bail!("ensemble 中配置了 openai，但缺少 [ai.openai]");
+                            }
+                            "ollama" | "local" | "kimi" | "openai" => {}
+                            other => anyhow::bail!("ensemble 中包含不支持的子提供商: {}", other),
+                        }
+                    }
+                }
+                other => {
+                    anyhow::bail!(
+                        "不支持的 ai.provider: {}，支持: ollama, kimi, openai, ensemble",
+                        other
+                    );
                 }
-            }
-            _ => {
-                anyhow::bail!(
-                    "不支持的 ai.provider: {}，支持: ollama, kimi, openai",
-                    self.ai.provider
-                );
             }
         }
 
@@ -97,10 +174,56 @@ impl Config {
             anyhow::bail!("processing.min_confidence 必须在 0.0 到 1.0 之间");
         }
 
+        if self.processing.human_approval_timeout_seconds == 0 {
+            anyhow::bail!("processing.human_approval_timeout_seconds 必须大于 0");
+        }
+
+        if let Some(threshold) = self.processing.human_approval_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                anyhow::bail!("processing.human_approval_threshold 必须在 0.0 到 1.0 之间");
+            }
+        }
+
+        if let Some(proxy) = &self.telegram.proxy {
+            validate_proxy_url("telegram.proxy", proxy)?;
+        }
+
+        if let Some(proxy) = &self.ai.proxy {
+            validate_proxy_url("ai.proxy", proxy)?;
+        }
+
+        // 验证 sink 配置
+        for (i, sink) in self.sinks.iter().enumerate() {
+            match sink.kind.as_str() {
+                "telegram" => {}
+                "webhook" => {
+                    let url = sink.url.as_ref()
+                        .ok_or_else(|| anyhow::anyhow!("sinks[{}] (webhook) 缺少 url", i))?;
+                    if !(url.starts_with("http://") || url.starts_with("https://")) {
+                        anyhow::bail!("sinks[{}].url 必须以 http:// 或 https:// 开头: {}", i, url);
+                    }
+                }
+                other => anyhow::bail!("sinks[{}] 不支持的 type: {}，支持: telegram, webhook", i, other),
+            }
+        }
+
         Ok(())
     }
 }
 
+/// 校验代理 URL 的 scheme，只允许 `reqwest::Proxy::all` 能识别的几种
+fn validate_proxy_url(field: &str, proxy: &str) -> Result<()> {
+    let is_supported = ["socks5://", "socks5h://", "http://", "https://"]
+        .iter()
+        .any(|scheme| proxy.starts_with(scheme));
+
+    if !is_supported {
+        anyhow::bail!("{} 必须以 socks5://、socks5h://、http:// 或 https:// 开头: {}", field, proxy);
+    }
+
+    Ok(())
+}
+
 /// Telegram 配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramConfig {
@@ -109,12 +232,63 @@ pub struct TelegramConfig {
     pub session_file: String,
     pub source_channels: Vec<i64>,
     pub target_user: i64,
+
+    /// Bot API Token（用于 `TelegramBot` 转发消息、审批按钮等，与上面的
+    /// MTProto 用户账号凭据是两套独立的身份）
+    pub bot_token: String,
+
+    /// 是否启用原生 MTProto 采集（grammers），与 HTTP 推送入口并存
+    #[serde(default)]
+    pub mtproto_ingestion_enabled: bool,
+
+    /// 出站代理地址（可选），支持 `socks5://` 和 `http://`，可在 URL 中
+    /// 内嵌 `user:pass@` 凭据；未配置时直连 `api.telegram.org`
+    #[serde(default)]
+    pub proxy: Option<String>,
+
+    /// 允许通过 Bot 命令操作频道列表的管理员 chat id；留空时退化为
+    /// 仅 `target_user` 可用，兼容单用户场景
+    #[serde(default)]
+    pub admin_chat_ids: Vec<i64>,
+
+    /// 发送消息时使用的 `parse_mode`："MarkdownV2"、"HTML"，或留空/`None`
+    /// 表示不启用富文本解析（Telegram 按纯文本处理）
+    #[serde(default)]
+    pub parse_mode: Option<String>,
+
+    /// 是否在消息中禁用链接预览卡片
+    #[serde(default)]
+    pub disable_web_page_preview: bool,
+
+    /// 遇到 Telegram 429/5xx 时的最大重试次数（429 按 `retry_after`
+    /// 精确等待，5xx 按 1s/2s/4s... 指数退避，二者都受此值封顶）
+    #[serde(default = "TelegramConfig::default_max_retries")]
+    pub max_retries: u32,
+}
+
+impl TelegramConfig {
+    /// 解析出实际生效的管理员 chat id 列表
+    pub fn admin_ids(&self) -> Vec<i64> {
+        if self.admin_chat_ids.is_empty() {
+            vec![self.target_user]
+        } else {
+            self.admin_chat_ids.clone()
+        }
+    }
+
+    fn default_max_retries() -> u32 {
+        3
+    }
 }
 
 /// AI 服务配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIConfig {
-    /// 服务提供商: "ollama", "kimi", "openai"
+    /// 服务提供商: "ollama"、"kimi"、"openai"、"ensemble"，或一条故障
+    /// 转移链——逗号分隔字符串（如 `"ollama,kimi"`）或 TOML 列表
+    /// （如 `["ollama", "kimi"]`），链上第一个是主提供商，其余按顺序
+    /// 作为失败后的备用提供商
+    #[serde(deserialize_with = "deserialize_provider")]
     pub provider: String,
 
     /// 超时时间（秒）
@@ -134,6 +308,47 @@ pub struct AIConfig {
 
     /// OpenAI API 配置（当 provider = "openai" 时生效）
     pub openai: Option<OpenAIConfig>,
+
+    /// 多提供商共识投票配置（当 provider = "ensemble" 时生效）
+    pub ensemble: Option<EnsembleConfig>,
+
+    /// 出站代理地址（可选），支持 `socks5://` 和 `http://`，可在 URL 中
+    /// 内嵌 `user:pass@` 凭据；所有子提供商的 HTTP 客户端共用同一个代理
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+impl AIConfig {
+    /// 将 `provider` 解析为有序的故障转移链
+    ///
+    /// 按逗号拆分，去除每一项首尾空白并转小写；空字符串经过这一步后
+    /// 返回空列表，交由 `Config::validate` 判定为配置错误
+    pub fn provider_chain(&self) -> Vec<String> {
+        self.provider
+            .split(',')
+            .map(|p| p.trim().to_lowercase())
+            .filter(|p| !p.is_empty())
+            .collect()
+    }
+}
+
+/// 支持 `provider` 字段写成单个字符串或 TOML 列表，统一反序列化为
+/// 逗号分隔的字符串，交由 `AIConfig::provider_chain` 解析
+fn deserialize_provider<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ProviderValue {
+        Single(String),
+        Chain(Vec<String>),
+    }
+
+    match ProviderValue::deserialize(deserializer)? {
+        ProviderValue::Single(s) => Ok(s),
+        ProviderValue::Chain(list) => Ok(list.join(",")),
+    }
 }
 
 /// Ollama 本地配置
@@ -144,6 +359,74 @@ pub struct OllamaConfig {
 
     /// 模型名称，例如: "llama3:8b"
     pub model: String,
+
+    /// 健康检查发现 `model` 未安装时，是否自动触发 `/api/pull` 下载；
+    /// 默认关闭，缺失时直接返回列出已安装模型的明确错误
+    #[serde(default)]
+    pub auto_pull_model: bool,
+
+    /// 请求模式: "chat"（默认，走 `/api/chat`，system/user 角色分离）
+    /// 或 "generate"（legacy，走 `/api/generate` 的单一 prompt 拼接，
+    /// 供依赖旧行为的配置继续使用）
+    #[serde(default = "OllamaConfig::default_api_mode")]
+    pub api_mode: String,
+
+    /// `api_mode = "chat"` 时使用的系统提示词；未配置时使用内置默认值
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+
+    /// 采样与上下文控制选项，未配置的字段各自使用默认值
+    #[serde(default)]
+    pub options: OllamaOptionsConfig,
+}
+
+impl OllamaConfig {
+    fn default_api_mode() -> String {
+        "chat".to_string()
+    }
+}
+
+/// Ollama `options` 请求参数，对应 `/api/generate`、`/api/chat` 请求体
+/// 中的采样与上下文控制选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaOptionsConfig {
+    #[serde(default = "OllamaOptionsConfig::default_temperature")]
+    pub temperature: f32,
+
+    #[serde(default = "OllamaOptionsConfig::default_top_p")]
+    pub top_p: f32,
+
+    #[serde(default = "OllamaOptionsConfig::default_repeat_penalty")]
+    pub repeat_penalty: f32,
+
+    /// 上下文窗口大小（token 数）。Ollama 没有提供查询模型最大上下文
+    /// 的接口，默认取一个常见的保守值，按需在配置中覆盖
+    #[serde(default = "OllamaOptionsConfig::default_num_ctx")]
+    pub num_ctx: u32,
+
+    /// 最大生成 token 数，-1 表示不设上限（仍受 num_ctx 约束）
+    #[serde(default = "OllamaOptionsConfig::default_num_predict")]
+    pub num_predict: i32,
+}
+
+impl OllamaOptionsConfig {
+    fn default_temperature() -> f32 { 0.3 }
+    fn default_top_p() -> f32 { 0.9 }
+    fn default_repeat_penalty() -> f32 { 1.1 }
+    fn default_num_ctx() -> u32 { 4096 }
+    fn default_num_predict() -> i32 { -1 }
+}
+
+impl Default for OllamaOptionsConfig {
+    fn default() -> Self {
+        Self {
+            temperature: Self::default_temperature(),
+            top_p: Self::default_top_p(),
+            repeat_penalty: Self::default_repeat_penalty(),
+            num_ctx: Self::default_num_ctx(),
+            num_predict: Self::default_num_predict(),
+        }
+    }
 }
 
 /// Kimi API 配置
@@ -157,6 +440,14 @@ pub struct KimiConfig {
 
     /// 基础 URL，默认: "https://api.moonshot.cn/v1"
     pub base_url: String,
+
+    /// 输入 token 单价（美元 / 1K tokens），用于成本估算，未配置时按 0 计算
+    #[serde(default)]
+    pub input_price_per_1k: f64,
+
+    /// 输出 token 单价（美元 / 1K tokens），用于成本估算，未配置时按 0 计算
+    #[serde(default)]
+    pub output_price_per_1k: f64,
 }
 
 impl KimiConfig {
@@ -177,6 +468,14 @@ pub struct OpenAIConfig {
     /// 基础 URL，默认: "https://api.openai.com/v1"
     /// 可以替换为其他兼容接口（如 DeepSeek）
     pub base_url: String,
+
+    /// 输入 token 单价（美元 / 1K tokens），用于成本估算，未配置时按 0 计算
+    #[serde(default)]
+    pub input_price_per_1k: f64,
+
+    /// 输出 token 单价（美元 / 1K tokens），用于成本估算，未配置时按 0 计算
+    #[serde(default)]
+    pub output_price_per_1k: f64,
 }
 
 impl OpenAIConfig {
@@ -185,6 +484,50 @@ impl OpenAIConfig {
     }
 }
 
+/// 多提供商共识投票配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnsembleConfig {
+    /// 参与投票的子提供商列表，例如: ["ollama", "kimi", "openai"]
+    pub providers: Vec<String>,
+
+    /// 最少需要在截止时间内返回结果的提供商数量，低于此值则退化为
+    /// 采用单个置信度最高的结果
+    pub quorum: usize,
+}
+
+/// 持久化存储配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// SQLite 数据库文件路径，例如: "data/tg-monitor.db"
+    pub db_path: String,
+}
+
+/// 单个输出 sink 的配置（`[[sinks]]`）
+///
+/// 用一个扁平结构承载所有 sink 类型的字段，而不是按 `ai.ollama`/
+/// `ai.kimi` 那样每种类型一个子结构体——sink 数量不固定、以数组形式
+/// 出现，`type` 字段决定实际生效哪些字段，未用到的留空即可。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SinkConfig {
+    /// sink 类型: "telegram" 或 "webhook"
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    /// webhook 目标地址（当 type = "webhook" 时必填）
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// webhook 请求的 `Authorization: Bearer` 令牌（可选）
+    #[serde(default)]
+    pub bearer_token: Option<String>,
+
+    /// webhook 请求体的 HMAC-SHA256 签名密钥（可选）；配置后会在
+    /// `X-Signal-Signature-256: sha256=<hex>` 请求头里附上签名，供
+    /// 接收端校验请求确实来自本服务且未被篡改
+    #[serde(default)]
+    pub sha256_secret: Option<String>,
+}
+
 /// 消息处理配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessingConfig {
@@ -199,4 +542,109 @@ pub struct ProcessingConfig {
 
     /// 关键词过滤（可选），包含这些词的消息优先处理
     pub keywords: Vec<String>,
+
+    /// 触发人工审批的置信度阈值（0.0-1.0，可选）。相关结果的置信度达到
+    /// 此阈值时，会通过 Telegram 内联键盘征求人工“买入/观察/忽略”决定，
+    /// 未配置时不启用人工审批，所有相关结果直接放行
+    #[serde(default)]
+    pub human_approval_threshold: Option<f32>,
+
+    /// 监控频道列表的持久化 JSON 文件路径（可选）。配置后，
+    /// `MessageProcessor` 启动时从该文件读回频道列表，并在每次
+    /// `add_channel`/`remove_channel`/`update_channels` 之后重写；
+    /// 未配置时频道列表只存在于内存中，重启即丢失
+    #[serde(default)]
+    pub channels_store: Option<String>,
+
+    /// 等待人工审批决定的最长时间（秒）。超时后按 `Action::Watch` 放行，
+    /// 避免管理员离线/按钮消息丢失时批处理任务永久挂起，阻塞后续所有
+    /// 频道的消息分析
+    #[serde(default = "ProcessingConfig::default_human_approval_timeout_seconds")]
+    pub human_approval_timeout_seconds: u64,
+}
+
+impl ProcessingConfig {
+    fn default_human_approval_timeout_seconds() -> u64 {
+        300
+    }
+}
+
+/// 展开配置文本里形如 `${VAR_NAME}` 的占位符，替换为同名环境变量的值；
+/// 环境变量不存在时返回明确指出变量名的错误，而不是让 TOML 解析报一个
+/// 无关的语法错误
+fn expand_env_vars(content: &str) -> Result<String> {
+    let re = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("环境变量占位符正则编译失败");
+
+    let mut missing = None;
+    let expanded = re.replace_all(content, |caps: &regex::Captures| {
+        let var_name = &caps[1];
+        env::var(var_name).unwrap_or_else(|_| {
+            missing.get_or_insert_with(|| var_name.to_string());
+            String::new()
+        })
+    });
+
+    if let Some(var_name) = missing {
+        anyhow::bail!("配置文件引用了未设置的环境变量: ${{{}}}", var_name);
+    }
+
+    Ok(expanded.into_owned())
+}
+
+/// 递归合并两个 TOML 表：`override_value` 中存在的键覆盖 `base` 中的同名
+/// 键，其余保留 `base` 原值；用于把 `secrets.toml` 里的敏感字段叠加到
+/// 主配置上
+fn merge_toml(base: &mut toml::Value, override_value: toml::Value) {
+    match (base, override_value) {
+        (toml::Value::Table(base_table), toml::Value::Table(override_table)) => {
+            for (key, value) in override_table {
+                match base_table.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base_table.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, override_value) => *base = override_value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_env_vars_substitutes_known_variable() {
+        env::set_var("TG_MONITOR_TEST_VAR", "secret-value");
+        let expanded = expand_env_vars("token = \"${TG_MONITOR_TEST_VAR}\"").unwrap();
+        assert_eq!(expanded, "token = \"secret-value\"");
+        env::remove_var("TG_MONITOR_TEST_VAR");
+    }
+
+    #[test]
+    fn test_expand_env_vars_rejects_missing_variable() {
+        let err = expand_env_vars("token = \"${TG_MONITOR_DOES_NOT_EXIST}\"").unwrap_err();
+        assert!(err.to_string().contains("TG_MONITOR_DOES_NOT_EXIST"));
+    }
+
+    #[test]
+    fn test_merge_toml_overrides_nested_keys_only() {
+        let mut base: toml::Value = toml::from_str(
+            "[telegram]\nbot_token = \"placeholder\"\napi_hash = \"placeholder\"\n",
+        )
+        .unwrap();
+        let secrets: toml::Value = toml::from_str("[telegram]\nbot_token = \"real-token\"\n").unwrap();
+
+        merge_toml(&mut base, secrets);
+
+        assert_eq!(
+            base["telegram"]["bot_token"].as_str(),
+            Some("real-token")
+        );
+        assert_eq!(
+            base["telegram"]["api_hash"].as_str(),
+            Some("placeholder")
+        );
+    }
 }
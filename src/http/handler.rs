@@ -19,6 +19,14 @@ pub struct ReceiveMessageRequest {
     pub text: String,
     pub timestamp: i64,
     pub sender: Option<String>,
+
+    /// 媒体类型（如果有），例如 "photo"
+    #[serde(default)]
+    pub media_type: Option<String>,
+
+    /// 媒体原始数据的 base64 编码（如果有），与 `media_type` 成对出现
+    #[serde(default)]
+    pub media_base64: Option<String>,
 }
 
 /// 响应体
@@ -169,6 +177,16 @@ async fn process_with_safety_checks(
     processor: Arc<MessageProcessor>,
     request: ReceiveMessageRequest,
 ) -> Result<ApiResponse, String> {
+    // 媒体负载以 base64 文本形式传输，在进入 panic 捕获区之前解码好，
+    // 解码失败只是丢弃媒体本身，不应该让整条消息处理失败
+    let media_data = request.media_base64.as_deref().and_then(|encoded| {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| warn!("⚠️  媒体 base64 解码失败，忽略媒体: {}", e))
+            .ok()
+    });
+
     // 单独的panic捕获区，专门针对消息转换
     match catch_unwind(AssertUnwindSafe(|| {
         // 进行更保守的数据清理
@@ -183,7 +201,8 @@ async fn process_with_safety_checks(
             text: safe_text,
             timestamp: request.timestamp,
             sender: request.sender.clone(),
-            media_type: None,
+            media_type: request.media_type.clone(),
+            media_data,
         };
         info!("✅ Message结构体构建完成：ID={}", message.id);
         Ok::<Message, String>(message)
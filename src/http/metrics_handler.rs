@@ -0,0 +1,18 @@
+use crate::http::channel_handler::ChannelApiResponse;
+use crate::processor::MessageProcessor;
+use axum::extract::State;
+use axum::response::IntoResponse;
+use std::sync::Arc;
+use tracing::debug;
+
+/// 获取当前 AI 服务的 token 用量、估算成本与请求速率
+pub async fn get_ai_usage(
+    State(processor): State<Arc<MessageProcessor>>,
+) -> impl IntoResponse {
+    debug!("获取 AI 用量指标");
+
+    match processor.ai_usage_snapshot() {
+        Some(snapshot) => ChannelApiResponse::success(snapshot, "获取用量指标成功"),
+        None => ChannelApiResponse::error("当前 AI 服务未提供用量统计（例如 ensemble/fallback 组合服务）"),
+    }
+}
@@ -0,0 +1,6 @@
+pub mod channel_handler;
+pub mod handler;
+pub mod metrics_handler;
+pub mod server;
+pub mod stream_handler;
+pub mod summary_handler;
@@ -1,4 +1,4 @@
-use crate::http::{handler, channel_handler};
+use crate::http::{handler, channel_handler, metrics_handler, stream_handler, summary_handler};
 use crate::processor::MessageProcessor;
 use axum::{
     routing::{get, post, put, delete},
@@ -41,6 +41,14 @@ impl HttpServer {
             .route("/api/v1/channels", put(channel_handler::update_channels))
             .route("/api/v1/channels/:channel_id", delete(channel_handler::remove_channel))
             .route("/api/v1/channels/:channel_id/check", get(channel_handler::check_channel))
+            // 实时分析结果订阅（SSE）
+            .route("/stream", get(stream_handler::stream_analysis))
+            // AI 用量/成本/速率指标
+            .route("/api/v1/metrics/usage", get(metrics_handler::get_ai_usage))
+            // 立即触发一次汇总报告
+            .route("/api/v1/summary/now", post(summary_handler::trigger_summary))
+            // 查询任意历史时间窗口的汇总报告
+            .route("/api/v1/summary/history", get(summary_handler::historical_summary))
             .layer(cors)
             .with_state(self.processor.clone());
 
@@ -57,6 +65,10 @@ impl HttpServer {
         info!("  - PUT  /api/v1/channels                 - 更新频道列表");
         info!("  - DELETE /api/v1/channels/:channel_id   - 删除频道");
         info!("  - GET  /api/v1/channels/:channel_id/check - 检查频道");
+        info!("  - GET  /stream                          - 实时分析结果订阅 (SSE)");
+        info!("  - GET  /api/v1/metrics/usage            - AI 用量/成本/速率指标");
+        info!("  - POST /api/v1/summary/now              - 立即触发一次汇总报告");
+        info!("  - GET  /api/v1/summary/history           - 查询历史时间窗口汇总报告");
         info!("========================================\n");
 
         // 启动服务器
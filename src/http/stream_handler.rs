@@ -0,0 +1,80 @@
+use crate::processor::{AnalysisEvent, MessageProcessor};
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::stream::Stream;
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt as TokioStreamExt;
+use tracing::warn;
+
+/// `/stream` 的查询参数：允许客户端只订阅感兴趣的子集
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    /// 只推送置信度不低于该值的结果
+    #[serde(default)]
+    pub min_confidence: Option<f32>,
+
+    /// 只推送来自该频道的结果
+    #[serde(default)]
+    pub channel_id: Option<i64>,
+}
+
+/// 订阅实时分析结果流（SSE）
+///
+/// 每条完成的 `AnalysisResult` 都会作为一个 `analysis` 事件推送给
+/// 所有连接的客户端；多个消费者（告警机器人、前端面板）可以同时
+/// 订阅，而不需要轮询 HTTP 接口。
+pub async fn stream_analysis(
+    State(processor): State<Arc<MessageProcessor>>,
+    Query(query): Query<StreamQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = processor.subscribe_events();
+    let stream = BroadcastStream::new(receiver).filter_map(move |item| {
+        match item {
+            Ok(event) => filter_event(&event, &query).map(to_sse_event),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("SSE 订阅者处理太慢，跳过了 {} 条事件", skipped);
+                Some(Ok(Event::default()
+                    .comment(format!("skipped {} events", skipped))))
+            }
+            Err(broadcast::error::RecvError::Closed) => None,
+        }
+    });
+
+    Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(Duration::from_secs(15))
+            .text("keep-alive"),
+    )
+}
+
+fn filter_event(event: &AnalysisEvent, query: &StreamQuery) -> Option<AnalysisEvent> {
+    if let Some(min_confidence) = query.min_confidence {
+        if event.result.confidence < min_confidence {
+            return None;
+        }
+    }
+
+    if let Some(channel_id) = query.channel_id {
+        if event.channel_id != channel_id {
+            return None;
+        }
+    }
+
+    Some(event.clone())
+}
+
+fn to_sse_event(event: AnalysisEvent) -> Result<Event, Infallible> {
+    let payload = serde_json::to_string(&event).unwrap_or_else(|e| {
+        warn!("序列化 AnalysisEvent 失败: {}", e);
+        "{}".to_string()
+    });
+
+    Ok(Event::default().event("analysis").data(payload))
+}
@@ -0,0 +1,43 @@
+use crate::http::channel_handler::ChannelApiResponse;
+use crate::processor::MessageProcessor;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 立即生成并转发一次汇总报告，不必等待 `batch_timeout_seconds` 定时触发
+pub async fn trigger_summary(
+    State(processor): State<Arc<MessageProcessor>>,
+) -> impl IntoResponse {
+    info!("收到立即生成汇总报告的请求");
+
+    match processor.force_summary().await {
+        Ok(_) => ChannelApiResponse::success((), "已触发汇总报告"),
+        Err(e) => ChannelApiResponse::error(format!("生成汇总报告失败: {}", e)),
+    }
+}
+
+/// 历史汇总报告查询参数：Unix 秒级时间戳 `[since, until)`
+#[derive(Deserialize)]
+pub struct HistoricalSummaryQuery {
+    pub since: i64,
+    pub until: i64,
+}
+
+/// 基于持久化存储查询任意历史时间窗口的汇总报告（未配置 `[storage]` 时报错）
+pub async fn historical_summary(
+    State(processor): State<Arc<MessageProcessor>>,
+    Query(query): Query<HistoricalSummaryQuery>,
+) -> impl IntoResponse {
+    info!("收到历史汇总报告查询请求: [{}, {})", query.since, query.until);
+
+    match processor.historical_summary(query.since, query.until) {
+        Ok(Some(report)) => ChannelApiResponse::success(report, "已生成历史汇总报告"),
+        Ok(None) => ChannelApiResponse::error("未配置 [storage]，无法查询历史汇总报告"),
+        Err(e) => {
+            warn!("查询历史汇总报告失败: {}", e);
+            ChannelApiResponse::error(format!("查询历史汇总报告失败: {}", e))
+        }
+    }
+}
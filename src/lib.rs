@@ -2,9 +2,12 @@
 // This allows integration tests to access internal modules
 
 pub mod ai;
+pub mod channel_store;
 pub mod config;
 pub mod http;
 pub mod processor;
+pub mod sinks;
+pub mod storage;
 pub mod telegram;
 
 // Unicode安全工具
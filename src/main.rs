@@ -3,11 +3,12 @@ use tg_meme_token_monitor::{
     config::Config,
     http::HttpServer,
     processor::MessageProcessor,
+    sinks::SinkFactory,
     telegram::bot::TelegramBot,
 };
 use anyhow::Result;
 use std::sync::Arc;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -31,6 +32,7 @@ async fn main() -> Result<()> {
     info!("  AI 服务: {}", config.ai.provider);
     info!("  HTTP 端口: {}", config.http.port);
     info!("  目标用户: {}", config.telegram.target_user);
+    info!("  Bot 命令管理员: {:?}", config.telegram.admin_ids());
     info!(
         "  批量处理: {} 条/{} 秒",
         config.processing.batch_size, config.processing.batch_timeout_seconds
@@ -69,11 +71,29 @@ async fn main() -> Result<()> {
         }
     }
 
+    // 初始化持久化存储（可选）
+    let storage = match &config.storage {
+        Some(storage_config) => {
+            info!("初始化存储子系统: {}", storage_config.db_path);
+            Some(Arc::new(tg_meme_token_monitor::storage::Storage::open(&storage_config.db_path)?))
+        }
+        None => {
+            warn!("⚠️  未配置 [storage]，分析结果将仅保存在内存中");
+            None
+        }
+    };
+
+    // 创建输出 sink（未配置 [[sinks]] 时退化为单个 telegram sink）
+    let sinks = SinkFactory::create_all(&config.sinks, Arc::clone(&telegram_bot))?;
+    info!("✓ 已加载 {} 个输出 sink", sinks.len());
+
     // 创建消息处理器
-    let message_processor = Arc::new(MessageProcessor::new(
+    let message_processor = Arc::new(MessageProcessor::with_storage(
         config.clone(),
         ai_service.into(),
-        telegram_bot,
+        Arc::clone(&telegram_bot),
+        sinks,
+        storage,
     ));
 
     // 启动消息处理器的后台任务
@@ -81,12 +101,33 @@ async fn main() -> Result<()> {
     message_processor.start().await?;
     info!("✓ 消息处理器已启动");
 
+    // 启动 Telegram 命令长轮询：分发 /channels /add /remove /status /summary，
+    // 并承载人工审批按钮的回调
+    info!("启动 Telegram 命令监听（getUpdates 长轮询）...");
+    telegram_bot.run_command_loop(message_processor.clone());
+
+    // 如果启用了原生 MTProto 采集，与 HTTP 推送入口并行运行
+    if config.telegram.mtproto_ingestion_enabled {
+        info!("启动原生 Telegram MTProto 采集客户端...");
+        let mut mtproto_client = tg_meme_token_monitor::telegram::client::Client::new(
+            config.telegram.clone(),
+            message_processor.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(e) = mtproto_client.start().await {
+                warn!("MTProto 采集客户端退出: {}", e);
+            }
+        });
+    } else {
+        debug!("未启用 telegram.mtproto_ingestion_enabled，仅通过 HTTP 接收消息");
+    }
+
     // 创建并启动 HTTP 服务器
     info!("启动 HTTP 服务器...");
     let http_server = HttpServer::new(message_processor, config.http.port);
 
     info!("✓ HTTP 服务器创建成功");
-    info!("等待接收来自 Python 监控器的消息...");
+    info!("等待接收来自 Python 监控器或 MTProto 客户端的消息...");
     info!("========================================\n");
 
     // 启动 HTTP 服务器（这会阻塞直到出错或用户中断）
@@ -1,57 +1,172 @@
 use crate::ai::AIService;
+use crate::channel_store::ChannelStore;
 use crate::config::Config;
 use crate::ai::models::{Message, AnalysisResult, TokenInfo, SummaryReport};
 use crate::http::channel_handler::ChannelInfo;
-use crate::telegram::bot::TelegramBot;
+use crate::sinks::Sink;
+use crate::storage::Storage;
+use crate::telegram::bot::{escape_markdown_v2, Action, TelegramBot};
 use crate::unicode_safe::{create_safe_summary, safe_log_message, normalize_for_logging};
 use anyhow::Result;
-use std::collections::{HashMap, VecDeque};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc, Mutex};
 use tokio::time::{interval, Duration};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+/// 分析完成后广播到事件总线上的事件
+///
+/// 携带触发分析的消息来源信息，便于 `/stream` 的订阅者按
+/// `channel_id` 过滤，而不需要改动 `AnalysisResult` 本身。
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AnalysisEvent {
+    pub channel_id: i64,
+    pub message_id: i64,
+    pub result: AnalysisResult,
+}
+
+/// 广播总线的订阅容量：超过这个数量的未消费事件会触发 `Lagged`
+const EVENT_BUS_CAPACITY: usize = 256;
+
+/// 待处理消息通道的缓冲容量：超过后 `process_message` 会在 `send` 上等待，
+/// 形成自然的反压，而不是无限堆积在内存里的 `VecDeque`
+const MESSAGE_CHANNEL_CAPACITY: usize = 256;
+
+/// 已批准分析结果广播总线的订阅容量
+const RESULTS_BUS_CAPACITY: usize = 256;
 
 /// 消息处理器
 pub struct MessageProcessor {
     config: Config,
     ai_service: Arc<dyn AIService>,
     telegram_bot: Arc<TelegramBot>,
-    message_queue: Arc<Mutex<VecDeque<Message>>>,
+    /// 消息生产端：`process_message` 把消息送进来，`start()` 取走对应的
+    /// 接收端交给批处理任务消费。`stop()` 丢弃发送端来关闭通道，驱动
+    /// 批处理任务在处理完最后一批后自然退出，不再需要轮询 `is_running`
+    message_tx: Arc<Mutex<Option<mpsc::Sender<Message>>>>,
+    /// 消息接收端，只会被 `start()` 取走一次
+    message_rx: Arc<Mutex<Option<mpsc::Receiver<Message>>>>,
+    /// 本轮汇总报告待发送的分析结果，由 `results_bus` 的一个订阅任务
+    /// （`collect_results_loop`）持续填充，`send_summary_report` 每轮取走
     analysis_results: Arc<Mutex<Vec<AnalysisResult>>>,
     is_running: Arc<Mutex<bool>>,
     /// 监控频道列表
     monitored_channels: Arc<Mutex<Vec<ChannelInfo>>>,
+    /// 频道列表的 JSON 文件持久化（未配置 `processing.channels_store` 时
+    /// 为 `None`，频道列表只存在于内存中，重启即丢失）
+    channel_store: Option<ChannelStore>,
+    /// 实时分析结果广播总线，供 `/stream` 等订阅者使用
+    event_bus: broadcast::Sender<AnalysisEvent>,
+    /// 人工审批通过的相关分析结果广播总线；报告汇总只是众多可能的订阅者
+    /// 之一，未来的 webhook 通知、指标采集都可以各自再订阅一路
+    results_bus: broadcast::Sender<AnalysisResult>,
+    /// 持久化存储（可选，未配置 `[storage]` 时为 `None`，退化为纯内存运行）
+    storage: Option<Arc<Storage>>,
+    /// 已配置的输出 sink，汇总报告生成后并发投递给每一个；未配置
+    /// `[[sinks]]` 时由 `SinkFactory::create_all` 退化为单个 telegram sink
+    sinks: Arc<Vec<Box<dyn Sink>>>,
+    /// 自上一次汇总报告以来处理过的消息总数
+    total_messages: Arc<AtomicU64>,
+    /// 自上一次汇总报告以来判定为相关的消息数
+    relevant_messages: Arc<AtomicU64>,
 }
 
 impl MessageProcessor {
     /// 创建新的消息处理器
-    pub fn new(config: Config, ai_service: Arc<dyn AIService>, telegram_bot: Arc<TelegramBot>) -> Self {
+    pub fn new(
+        config: Config,
+        ai_service: Arc<dyn AIService>,
+        telegram_bot: Arc<TelegramBot>,
+        sinks: Vec<Box<dyn Sink>>,
+    ) -> Self {
+        Self::with_storage(config, ai_service, telegram_bot, sinks, None)
+    }
+
+    /// 创建带持久化存储的消息处理器
+    pub fn with_storage(
+        config: Config,
+        ai_service: Arc<dyn AIService>,
+        telegram_bot: Arc<TelegramBot>,
+        sinks: Vec<Box<dyn Sink>>,
+        storage: Option<Arc<Storage>>,
+    ) -> Self {
+        let (event_bus, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        let (results_bus, _) = broadcast::channel(RESULTS_BUS_CAPACITY);
+        let (message_tx, message_rx) = mpsc::channel(MESSAGE_CHANNEL_CAPACITY);
+
+        let channel_store = config.processing.channels_store.as_ref().map(ChannelStore::new);
+        let initial_channels = match &channel_store {
+            Some(store) => match store.load() {
+                Ok(channels) => {
+                    info!("从持久化文件恢复了 {} 个监控频道", channels.len());
+                    channels
+                }
+                Err(e) => {
+                    error!("读取持久化频道列表失败，以空列表启动: {}", e);
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+
         Self {
             config,
             ai_service,
             telegram_bot,
-            message_queue: Arc::new(Mutex::new(VecDeque::new())),
+            message_tx: Arc::new(Mutex::new(Some(message_tx))),
+            message_rx: Arc::new(Mutex::new(Some(message_rx))),
             analysis_results: Arc::new(Mutex::new(Vec::new())),
             is_running: Arc::new(Mutex::new(false)),
-            monitored_channels: Arc::new(Mutex::new(Vec::new())),
+            monitored_channels: Arc::new(Mutex::new(initial_channels)),
+            channel_store,
+            event_bus,
+            results_bus,
+            storage,
+            sinks: Arc::new(sinks),
+            total_messages: Arc::new(AtomicU64::new(0)),
+            relevant_messages: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// 把当前频道列表原子地重写到持久化文件（未配置 `channels_store`
+    /// 时是空操作）
+    fn persist_channels(&self, channels: &[ChannelInfo]) -> Result<()> {
+        match &self.channel_store {
+            Some(store) => store.save(channels),
+            None => Ok(()),
+        }
+    }
+
+    /// 订阅实时分析结果事件流
+    pub fn subscribe_events(&self) -> broadcast::Receiver<AnalysisEvent> {
+        self.event_bus.subscribe()
+    }
+
     /// 启动处理器（后台任务）
     pub async fn start(&self) -> Result<()> {
         *self.is_running.lock().await = true;
 
         info!("启动消息处理器...");
 
-        // 启动两个后台任务：
-        // 1. 消息处理任务 - 持续处理队列中的消息
-        // 2. 定时报告任务 - 定期发送汇总报告
+        let receiver = self.message_rx.lock().await.take();
+        let receiver = receiver
+            .ok_or_else(|| anyhow::anyhow!("消息处理器已经启动过，不能重复 start()"))?;
+
+        // 启动三个后台任务：
+        // 1. 消息批处理任务 - 从 mpsc 通道攒批并分析
+        // 2. 结果汇总任务 - 订阅已批准结果，供定时报告使用
+        // 3. 定时报告任务 - 定期发送汇总报告
 
         let processor = self.clone();
         tokio::spawn(async move {
-            if let Err(e) = processor.processing_loop().await {
-                error!("消息处理循环出错: {}", e);
-            }
+            processor.batch_processing_loop(receiver).await;
+        });
+
+        let collector = self.clone();
+        tokio::spawn(async move {
+            collector.collect_results_loop().await;
         });
 
         let reporter = self.clone();
@@ -66,8 +181,12 @@ impl MessageProcessor {
     }
 
     /// 停止处理器
+    ///
+    /// 丢弃消息发送端以关闭通道：批处理任务在消费完通道中剩余的消息后，
+    /// `recv()` 会收到 `None` 并自然退出，不需要额外的停止信号
     pub async fn stop(&self) {
         *self.is_running.lock().await = false;
+        self.message_tx.lock().await.take();
         info!("消息处理器已停止");
     }
 
@@ -76,8 +195,25 @@ impl MessageProcessor {
         *self.is_running.lock().await
     }
 
+    /// 当前待处理队列长度（供 `/status` 等运维命令查询）
+    ///
+    /// 通道没有长度方法，用已用容量近似：`max_capacity - capacity`
+    pub async fn queue_len(&self) -> usize {
+        match self.message_tx.lock().await.as_ref() {
+            Some(sender) => sender.max_capacity() - sender.capacity(),
+            None => 0,
+        }
+    }
+
+    /// 立即生成并发送一次汇总报告，跳过定时等待（供 `/summary` 命令使用）
+    pub async fn force_summary(&self) -> Result<()> {
+        self.send_summary_report().await
+    }
+
     /// 处理消息（从 Telegram 接收）
     pub async fn process_message(&self, message: Message) -> Result<()> {
+        self.total_messages.fetch_add(1, Ordering::Relaxed);
+
         // 使用Unicode安全的日志记录
         let safe_summary = create_safe_summary(&message.text);
         info!("🎯 MESSAGE PROCESSOR: process_message() 被调用！消息: [{}] {} - {}",
@@ -92,50 +228,74 @@ impl MessageProcessor {
 
         info!("✅ 消息通过关键词过滤");
 
-        // 将消息加入队列
-        info!("📥 将消息加入处理队列...");
-        self.message_queue.lock().await.push_back(message);
-        info!("✓ 消息已加入处理队列");
-
-        // 如果队列达到批量大
-        let queue_size = self.message_queue.lock().await.len();
-        info!("📊 当前队列大小: {}", queue_size);
-        if queue_size >= self.config.processing.batch_size {
-            info!("🚀 队列达到批量大小 ({}), 触发处理", queue_size);
-            self.process_queue().await?;
-        } else {
-            info!("⏳ 队列未达到批量大小，等待更多消息");
-        }
+        // 送入消息通道，由批处理任务攒批消费，不再自己维护队列长度触发
+        info!("📥 将消息送入处理通道...");
+        let sender = self.message_tx.lock().await.clone();
+        let Some(sender) = sender else {
+            anyhow::bail!("消息处理器尚未启动或已停止，无法接收新消息");
+        };
+        sender
+            .send(message)
+            .await
+            .map_err(|_| anyhow::anyhow!("消息批处理任务已退出，发送失败"))?;
+        info!("✓ 消息已送入处理通道");
 
         Ok(())
     }
 
-    /// 消息处理循环
-    async fn processing_loop(&self) -> Result<()> {
-        info!("启动消息处理循环");
+    /// 消息批处理循环：从 `mpsc` 通道中攒批，达到 `batch_size` 或等待超过
+    /// `batch_timeout_seconds` 没有新消息时立即处理当前批次，替代原先固定
+    /// 5 秒轮询一次共享 `VecDeque` 的方式，消除轮询延迟和锁竞争
+    async fn batch_processing_loop(&self, mut receiver: mpsc::Receiver<Message>) {
+        info!("启动消息批处理循环");
 
-        let mut check_interval = interval(Duration::from_secs(5));
+        let batch_timeout = Duration::from_secs(self.config.processing.batch_timeout_seconds);
+        let mut batch: Vec<Message> = Vec::new();
 
         loop {
-            tokio::select! {
-                _ = check_interval.tick() => {
-                    if !*self.is_running.lock().await {
-                        break;
+            match tokio::time::timeout(batch_timeout, receiver.recv()).await {
+                Ok(Some(message)) => {
+                    batch.push(message);
+                    if batch.len() >= self.config.processing.batch_size {
+                        debug!("批次达到批量大小 ({}), 立即处理", batch.len());
+                        self.process_batch(std::mem::take(&mut batch)).await;
                     }
-
-                    let queue_size = self.message_queue.lock().await.len();
-                    if queue_size > 0 {
-                        debug!("处理队列中的 {} 条消息", queue_size);
-                        if let Err(e) = self.process_queue().await {
-                            error!("处理队列失败: {}", e);
-                        }
+                }
+                Ok(None) => {
+                    info!("消息通道已关闭，处理完剩余批次后退出批处理循环");
+                    if !batch.is_empty() {
+                        self.process_batch(std::mem::take(&mut batch)).await;
+                    }
+                    break;
+                }
+                Err(_) => {
+                    if !batch.is_empty() {
+                        debug!("等待新消息超时，处理当前批次的 {} 条消息", batch.len());
+                        self.process_batch(std::mem::take(&mut batch)).await;
                     }
                 }
             }
         }
 
-        info!("消息处理循环已退出");
-        Ok(())
+        info!("消息批处理循环已退出");
+    }
+
+    /// 持续订阅 `results_bus`，把已批准的分析结果汇总进 `analysis_results`
+    /// 供定时报告取走；通道关闭（所有发送端已被丢弃）时退出
+    async fn collect_results_loop(&self) {
+        let mut receiver = self.results_bus.subscribe();
+
+        loop {
+            match receiver.recv().await {
+                Ok(result) => {
+                    self.analysis_results.lock().await.push(result);
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("结果汇总订阅落后，丢失 {} 条分析结果", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
     }
 
     /// 定时报告循环
@@ -164,29 +324,82 @@ impl MessageProcessor {
         Ok(())
     }
 
-    /// 处理队列中的所有消息
-    pub async fn process_queue(&self) -> Result<()> {
-        let messages: Vec<Message> = {
-            let mut queue = self.message_queue.lock().await;
-            queue.drain(..).collect()
-        };
-
+    /// 处理一批消息：按频道分组后并发处理各组，组内逐条 AI 分析、
+    /// 相关且审批通过的结果广播到 `results_bus`，不再累积到共享 `Vec`
+    /// 里等轮询者来取
+    ///
+    /// 按频道分组是为了不让某一条消息卡在人工审批上时拖住同一批次里其余
+    /// 无关频道的分析；本函数要等所有频道组都结束才返回，所以卡住的那一组
+    /// 仍会推迟下一批次（包括其他频道的新消息）的处理
+    async fn process_batch(&self, messages: Vec<Message>) {
         if messages.is_empty() {
-            return Ok(());
+            return;
         }
 
         info!("开始批量处理 {} 条消息", messages.len());
 
-        let mut results = Vec::new();
+        let mut by_channel: HashMap<i64, Vec<Message>> = HashMap::new();
+        for message in messages {
+            by_channel.entry(message.channel_id).or_default().push(message);
+        }
+
+        let mut channel_tasks = FuturesUnordered::new();
+        for (_, channel_messages) in by_channel {
+            channel_tasks.push(self.process_channel_messages(channel_messages));
+        }
+
+        while channel_tasks.next().await.is_some() {}
+    }
+
+    /// 顺序处理同一频道的一组消息（组间由 `process_batch` 并发调度）
+    async fn process_channel_messages(&self, messages: Vec<Message>) {
         for message in messages {
             match self.analyze_message(&message).await {
                 Ok(analysis_result) => {
+                    if let Some(storage) = &self.storage {
+                        if let Err(e) = storage.record(&message, &analysis_result) {
+                            error!("持久化消息 [{}] {} 失败: {}", message.channel_id, message.id, e);
+                        }
+                    }
+
+                    // 无论是否相关，都发布到事件总线，订阅者自行按需过滤
+                    let event = AnalysisEvent {
+                        channel_id: message.channel_id,
+                        message_id: message.id,
+                        result: analysis_result.clone(),
+                    };
+                    // 没有订阅者时发送会失败，这是正常情况，忽略即可
+                    let _ = self.event_bus.send(event);
+
                     if analysis_result.is_relevant {
+                        self.relevant_messages.fetch_add(1, Ordering::Relaxed);
+
                         info!("发现相关消息:");
                         // 使用Unicode安全的日志记录，避免tracing内部UTF-8问题
                         let safe_summary = crate::unicode_safe::safe_log_message(&analysis_result.format_summary(), "analysis_summary");
                         info!("{}", safe_summary);
-                        results.push(analysis_result);
+
+                        let approved = if self.needs_human_approval(&analysis_result) {
+                            match self.request_human_approval(&analysis_result).await {
+                                Action::Ignore => {
+                                    info!("人工审批已忽略该 token: {:?}", analysis_result.token_name);
+                                    false
+                                }
+                                action => {
+                                    info!("人工审批通过（{:?}），保留该结果: {:?}", action, analysis_result.token_name);
+                                    true
+                                }
+                            }
+                        } else {
+                            true
+                        };
+
+                        if approved {
+                            self.forward_media_if_present(&message, &analysis_result).await;
+                            self.deliver_signal_to_sinks(&analysis_result).await;
+                            // 没有订阅者时发送会失败，这是正常情况，忽略即可
+                            let _ = self.results_bus.send(analysis_result);
+                        }
                     } else {
                         debug!("消息不是相关内容");
                     }
@@ -196,13 +409,6 @@ impl MessageProcessor {
                 }
             }
         }
-
-        // 将结果保存
-        if !results.is_empty() {
-            self.analysis_results.lock().await.extend(results);
-        }
-
-        Ok(())
     }
 
     /// 分析单条消息
@@ -221,6 +427,11 @@ impl MessageProcessor {
 
     /// 发送汇总报告
     async fn send_summary_report(&self) -> Result<()> {
+        // 无论本轮是否有相关结果可汇总，都重置计数器，保证下一轮统计的是
+        // 新周期内的消息，而不是持续累加
+        let total_messages = self.total_messages.swap(0, Ordering::Relaxed) as usize;
+        let relevant_messages = self.relevant_messages.swap(0, Ordering::Relaxed) as usize;
+
         let results: Vec<AnalysisResult> = {
             let mut stored_results = self.analysis_results.lock().await;
             if stored_results.is_empty() {
@@ -256,13 +467,14 @@ impl MessageProcessor {
         // 按提及次数排序
         tokens.sort_by(|a, b| b.mentions.cmp(&a.mentions));
 
-        // 创建报告
-        let report = SummaryReport {
+        // 创建报告，附带当前 AI 服务的用量/成本/重试率/延迟快照，便于
+        // 监控 AI 成本漂移与重试率异常抬升
+        let report = SummaryReport::with_ai_usage(
             tokens,
-            generated_at: chrono::Utc::now().timestamp(),
-            total_messages: 0,  // TODO: 需要正确统计
-            relevant_messages: 0,  // TODO: 需要正确统计
-        };
+            total_messages,
+            relevant_messages,
+            self.ai_service.usage_snapshot(),
+        );
 
         // 格式化并发送报告
         if !report.is_empty() {
@@ -272,7 +484,9 @@ impl MessageProcessor {
         Ok(())
     }
 
-    /// 发送报告（输出详细日志并转发到Telegram）
+    /// 发送报告：日志输出完整的 token 明细，但投递给 sink 的版本只含
+    /// 消息统计与 AI 用量汇总，避免和 [`Self::deliver_signal_to_sinks`]
+    /// 的实时投递重复通知同一个信号
     async fn send_report(&self, report: &SummaryReport) -> Result<()> {
         info!("========== AI 评估报告 ==========");
 
@@ -307,18 +521,169 @@ impl MessageProcessor {
             }
         }
 
-        // 转发报告到 Telegram 目标用户
-        if !report_content.is_empty() {
-            info!("正在转发报告到 Telegram 用户 {}...", self.config.telegram.target_user);
-            match self.telegram_bot.send_message(&report_content).await {
-                Ok(_) => info!("✓ 报告已成功转发到 Telegram 用户 {}", self.config.telegram.target_user),
-                Err(e) => error!("✗ 转发报告到 Telegram 失败: {}", e),
+        // 每个 token 在 process_channel_messages 审批通过的那一刻，就已经
+        // 由 deliver_signal_to_sinks 实时投递给各 sink 了；这里的周期性/
+        // 按需摘要如果把 report.tokens 原样再投递一遍，sink 那边会对同一个
+        // 信号收到两次通知。因此投递给 sink 的版本清空 tokens，只保留本轮
+        // 的消息统计与 AI 用量汇总，token 明细仍然完整写入上面的日志
+        let digest = SummaryReport::with_ai_usage(
+            Vec::new(),
+            report.total_messages,
+            report.relevant_messages,
+            report.ai_usage.clone(),
+        );
+
+        info!("正在投递报告到 {} 个 sink...", self.sinks.len());
+
+        let mut deliveries = FuturesUnordered::new();
+        for sink in self.sinks.iter() {
+            let name = sink.name();
+            deliveries.push(async move { (name, sink.deliver(&digest).await) });
+        }
+
+        while let Some((name, result)) = deliveries.next().await {
+            match result {
+                Ok(_) => info!("✓ 报告已投递到 sink: {}", name),
+                Err(e) => error!("✗ 投递到 sink {} 失败: {}", name, e),
             }
         }
 
         Ok(())
     }
 
+    /// 每条通过审批的信号立即并发投递到所有已配置的 sink，而不是等
+    /// `send_summary_report` 的周期性/按需摘要——否则接入 webhook sink
+    /// 的实时自动化服务只能等到下一个汇总周期才看到信号，单条确认
+    /// 的 token 提醒也就失去了时效性
+    ///
+    /// 没有 `token_name` 的结果（`TokenInfo::from_analysis` 返回 `None`）不投递
+    async fn deliver_signal_to_sinks(&self, result: &AnalysisResult) {
+        let Some(token_info) = TokenInfo::from_analysis(std::slice::from_ref(result)) else {
+            return;
+        };
+
+        let signal = SummaryReport::new(vec![token_info], 1, 1);
+
+        let mut deliveries = FuturesUnordered::new();
+        for sink in self.sinks.iter() {
+            let name = sink.name();
+            deliveries.push(async move { (name, sink.deliver(&signal).await) });
+        }
+
+        while let Some((name, result)) = deliveries.next().await {
+            match result {
+                Ok(_) => debug!("✓ 信号已实时投递到 sink: {}", name),
+                Err(e) => error!("✗ 实时投递信号到 sink {} 失败: {}", name, e),
+            }
+        }
+    }
+
+    /// 判断该分析结果是否需要人工审批
+    ///
+    /// 未配置 `processing.human_approval_threshold` 时始终返回
+    /// `false`，所有相关结果直接放行
+    fn needs_human_approval(&self, result: &AnalysisResult) -> bool {
+        self.config
+            .processing
+            .human_approval_threshold
+            .is_some_and(|threshold| result.confidence >= threshold)
+    }
+
+    /// 通过 Telegram 内联键盘向人工征求“买入/观察/忽略”决定
+    ///
+    /// 审批请求失败（例如回调监听未启动）或在
+    /// `processing.human_approval_timeout_seconds` 内始终没有人点击按钮
+    /// 时都默认按“观察”放行——`batch_processing_loop` 只有一个消费者，
+    /// 无限期挂起会让后续所有频道的消息都堆积在 mpsc 通道里，直至把
+    /// 生产端（HTTP 入口、MTProto 客户端）也一起阻塞
+    async fn request_human_approval(&self, result: &AnalysisResult) -> Action {
+        let prompt = Self::format_approval_prompt(result);
+        let timeout = Duration::from_secs(self.config.processing.human_approval_timeout_seconds);
+
+        match tokio::time::timeout(timeout, self.telegram_bot.prompt_decision(&prompt)).await {
+            Ok(Ok(action)) => action,
+            Ok(Err(e)) => {
+                error!("请求人工审批失败，默认按观察处理: {}", e);
+                Action::Watch
+            }
+            Err(_) => {
+                warn!(
+                    "等待人工审批超过 {} 秒，默认按观察处理: {:?}",
+                    self.config.processing.human_approval_timeout_seconds,
+                    result.token_name
+                );
+                Action::Watch
+            }
+        }
+    }
+
+    /// 构建发往 [`TelegramBot::prompt_decision`] 的审批提示文本
+    ///
+    /// `TelegramBot` 的 `parse_mode` 配置为 `MarkdownV2` 时，[`AnalysisResult::format_summary`]
+    /// 输出的 `**粗体**` 属于旧版 Markdown 语法，且直接拼入了未转义的
+    /// AI 生成字段（token_name/contract_address/reason），两者都会让
+    /// Telegram 以 "can't parse entities" 拒收整条消息。这里改为手写
+    /// MarkdownV2 合法的单星号粗体，并对每个插值字段单独调用
+    /// [`escape_markdown_v2`]，只转义不受信任/含格式字符的内容，不影响
+    /// 模板本身的 `*`/`` ` ``/`>` 语法字符
+    fn format_approval_prompt(result: &AnalysisResult) -> String {
+        let mut body = String::new();
+
+        if let Some(token_name) = &result.token_name {
+            body.push_str(&format!("> *Token*: {}\n", escape_markdown_v2(token_name)));
+        }
+
+        if let Some(contract) = &result.contract_address {
+            match &result.chain {
+                Some(chain) => body.push_str(&format!(
+                    "> *合约* \\({}\\): `{}`\n",
+                    escape_markdown_v2(chain),
+                    escape_markdown_v2(contract)
+                )),
+                None => body.push_str(&format!("> *合约*: `{}`\n", escape_markdown_v2(contract))),
+            }
+        }
+
+        body.push_str(&format!(
+            "> *建议*: {}\n",
+            escape_markdown_v2(&result.get_action_suggestion())
+        ));
+
+        if let Some(reason) = &result.reason {
+            if !reason.is_empty() {
+                body.push_str(&format!("> *理由*: {}\n", escape_markdown_v2(reason.trim())));
+            }
+        }
+
+        body.push_str(&format!(
+            "> *置信度*: {} \\| *紧急度*: {}/10\n",
+            escape_markdown_v2(&format!("{:.1}%", result.confidence * 100.0)),
+            result.urgency
+        ));
+
+        body.push_str(&format!("> *来源*: {}\n", escape_markdown_v2(&result.source)));
+
+        format!("⚠️ *需要人工审批*\n\n{}", body)
+    }
+
+    /// 若该消息携带图片数据，连同分析摘要一起以图片形式转发，保留文本转发
+    /// 会丢失的视觉上下文（例如图表、合约截图），而不是直接丢弃媒体
+    async fn forward_media_if_present(&self, message: &Message, result: &AnalysisResult) {
+        if !message.has_image_data() {
+            return;
+        }
+
+        let Some(image) = message.media_data.as_deref() else {
+            return;
+        };
+
+        let caption = result.format_summary();
+        match self.telegram_bot.send_photo(&caption, image).await {
+            Ok(_) => info!("✓ 已转发媒体消息 [{}] {}", message.channel_id, message.id),
+            Err(e) => error!("✗ 转发媒体消息失败 [{}] {}: {}", message.channel_id, message.id, e),
+        }
+    }
+
     /// 判断消息是否应该被过滤
     async fn should_filter(&self, message: &Message) -> bool {
         // 如果没有配置关键词，不过滤
@@ -366,7 +731,7 @@ impl MessageProcessor {
         channels.push(channel);
         info!("添加监控频道: {}", channel_id);
 
-        Ok(())
+        self.persist_channels(&channels)
     }
 
     /// 从监控列表中删除频道
@@ -380,32 +745,30 @@ impl MessageProcessor {
             info!("删除监控频道: {}", channel_id);
         }
 
-        Ok(())
+        self.persist_channels(&channels)
     }
 
     /// 更新整个频道列表
     pub async fn update_channels(&self, channel_ids: Vec<i64>) -> Result<()> {
         let mut channels = self.monitored_channels.lock().await;
 
-        // 保留现有的频道名称信息
-        let existing: std::collections::HashMap<i64, Option<String>> = channels.iter()
-            .map(|c| (c.channel_id, c.channel_name.clone()))
+        // 保留现有的频道名称和添加时间信息
+        let existing: std::collections::HashMap<i64, (Option<String>, i64)> = channels.iter()
+            .map(|c| (c.channel_id, (c.channel_name.clone(), c.added_at)))
             .collect();
 
         // 替换为新的频道列表
         *channels = channel_ids.into_iter()
             .map(|id| ChannelInfo {
                 channel_id: id,
-                channel_name: existing.get(&id).cloned().unwrap_or(None),
-                added_at: existing.get(&id).map_or(chrono::Utc::now().timestamp(), |_| {
-                    // 如果频道已存在，保留原添加时间
-                    chrono::Utc::now().timestamp()
-                }),
+                channel_name: existing.get(&id).and_then(|(name, _)| name.clone()),
+                // 如果频道已存在，保留原添加时间
+                added_at: existing.get(&id).map(|(_, ts)| *ts).unwrap_or_else(|| chrono::Utc::now().timestamp()),
             })
             .collect();
 
         info!("更新频道列表，共 {} 个频道", channels.len());
-        Ok(())
+        self.persist_channels(&channels)
     }
 
     /// 检查频道是否在监控列表中
@@ -413,6 +776,20 @@ impl MessageProcessor {
         let channels = self.monitored_channels.lock().await;
         Ok(channels.iter().any(|c| c.channel_id == channel_id))
     }
+
+    /// 获取当前 AI 服务的 token 用量/成本/速率快照（ensemble/fallback 组合服务为 `None`）
+    pub fn ai_usage_snapshot(&self) -> Option<crate::ai::metrics::UsageSnapshot> {
+        self.ai_service.usage_snapshot()
+    }
+
+    /// 基于持久化存储查询历史时间窗口 `[since, until)` 的汇总报告；
+    /// 未配置 `[storage]` 时返回 `None`，调用方需要退化为提示错误
+    pub fn historical_summary(&self, since: i64, until: i64) -> Result<Option<SummaryReport>> {
+        match &self.storage {
+            Some(storage) => SummaryReport::for_window(storage, since, until).map(Some),
+            None => Ok(None),
+        }
+    }
 }
 
 // 为 MessageProcessor 实现 Clone
@@ -422,10 +799,18 @@ impl Clone for MessageProcessor {
             config: self.config.clone(),
             ai_service: Arc::clone(&self.ai_service),
             telegram_bot: Arc::clone(&self.telegram_bot),
-            message_queue: Arc::clone(&self.message_queue),
+            message_tx: Arc::clone(&self.message_tx),
+            message_rx: Arc::clone(&self.message_rx),
             analysis_results: Arc::clone(&self.analysis_results),
             is_running: Arc::clone(&self.is_running),
             monitored_channels: Arc::clone(&self.monitored_channels),
+            channel_store: self.channel_store.clone(),
+            event_bus: self.event_bus.clone(),
+            results_bus: self.results_bus.clone(),
+            storage: self.storage.clone(),
+            sinks: Arc::clone(&self.sinks),
+            total_messages: Arc::clone(&self.total_messages),
+            relevant_messages: Arc::clone(&self.relevant_messages),
         }
     }
 }
@@ -433,10 +818,11 @@ impl Clone for MessageProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{AIConfig, KimiConfig};
-
-    // TODO: 添加测试用的 Mock AI Service
-    // 目前暂时跳过，因为需要 Mock trait object
+    use crate::ai::models::AIProvider;
+    use crate::ai::AIError;
+    use crate::config::{AIConfig, KimiConfig, ProcessingConfig, TelegramConfig};
+    use crate::sinks::Sink;
+    use async_trait::async_trait;
 
     #[tokio::test]
     async fn test_message_summary() {
@@ -448,6 +834,7 @@ mod tests {
             timestamp: 1234567890,
             sender: None,
             media_type: None,
+            media_data: None,
         };
 
         let summary = msg.summary();
@@ -455,4 +842,168 @@ mod tests {
         assert!(summary.contains("1"));
         assert!(summary.contains("..."));  // 应该被截断
     }
+
+    /// 测试用假 AI 服务：每次 analyze 都返回同一个预置的分析结果，
+    /// 不发起任何真实网络请求
+    struct FakeAIService {
+        result: AnalysisResult,
+    }
+
+    #[async_trait]
+    impl AIService for FakeAIService {
+        async fn analyze(&self, _message: &str) -> Result<AnalysisResult, AIError> {
+            Ok(self.result.clone())
+        }
+
+        async fn health_check(&self) -> bool {
+            true
+        }
+
+        fn name(&self) -> String {
+            "fake-ai-service".to_string()
+        }
+
+        fn provider(&self) -> AIProvider {
+            AIProvider::Kimi
+        }
+    }
+
+    /// 测试用假 sink：把每次收到的报告原样记录下来，供断言比对
+    struct FakeSink {
+        received: Arc<Mutex<Vec<SummaryReport>>>,
+    }
+
+    #[async_trait]
+    impl Sink for FakeSink {
+        async fn deliver(&self, signal: &SummaryReport) -> Result<()> {
+            self.received.lock().await.push(signal.clone());
+            Ok(())
+        }
+
+        async fn health_check(&self) -> bool {
+            true
+        }
+
+        fn name(&self) -> String {
+            "fake-sink".to_string()
+        }
+    }
+
+    fn test_config() -> Config {
+        Config {
+            telegram: TelegramConfig {
+                api_id: 1,
+                api_hash: "test-api-hash".to_string(),
+                session_file: "test-session".to_string(),
+                source_channels: vec![-100123],
+                target_user: 1,
+                bot_token: "TEST_BOT_TOKEN".to_string(),
+                mtproto_ingestion_enabled: false,
+                proxy: None,
+                admin_chat_ids: vec![],
+                parse_mode: None,
+                disable_web_page_preview: false,
+                max_retries: 1,
+            },
+            ai: AIConfig {
+                provider: "kimi".to_string(),
+                timeout_seconds: 5,
+                max_retries: 1,
+                prompt_template: "".to_string(),
+                kimi: Some(KimiConfig {
+                    api_key: "TEST_API_KEY".to_string(),
+                    model: "test-model".to_string(),
+                    base_url: "https://example.invalid".to_string(),
+                    input_price_per_1k: 0.0,
+                    output_price_per_1k: 0.0,
+                }),
+                ollama: None,
+                openai: None,
+                ensemble: None,
+                proxy: None,
+            },
+            processing: ProcessingConfig {
+                batch_size: 1,
+                batch_timeout_seconds: 1,
+                min_confidence: 0.0,
+                keywords: vec![],
+                human_approval_threshold: None,
+                channels_store: None,
+                human_approval_timeout_seconds: 5,
+            },
+            storage: None,
+            sinks: vec![],
+        }
+    }
+
+    fn relevant_result(token_name: &str) -> AnalysisResult {
+        AnalysisResult {
+            is_relevant: true,
+            token_name: Some(token_name.to_string()),
+            contract_address: None,
+            chain: None,
+            recommendation: Some("买入".to_string()),
+            reason: None,
+            confidence: 0.9,
+            urgency: 5,
+            source: "fake-ai-service".to_string(),
+            timestamp: 0,
+            raw_response: None,
+        }
+    }
+
+    /// `process_batch` 应该把审批通过（无需人工审批时默认通过）的相关
+    /// 结果立即投递给所有已配置的 sink
+    #[tokio::test]
+    async fn test_process_batch_delivers_approved_result_to_sinks() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let config = test_config();
+        let ai_service: Arc<dyn AIService> = Arc::new(FakeAIService {
+            result: relevant_result("TEST"),
+        });
+        let telegram_bot = Arc::new(TelegramBot::new(config.telegram.clone()));
+        let sink: Box<dyn Sink> = Box::new(FakeSink { received: Arc::clone(&received) });
+
+        let processor = MessageProcessor::new(config, ai_service, telegram_bot, vec![sink]);
+        let message = Message::new(1, -100123, "TestChannel".to_string(), "hello".to_string(), 0);
+
+        processor.process_batch(vec![message]).await;
+
+        let delivered = received.lock().await;
+        assert_eq!(delivered.len(), 1, "审批通过的信号应立即投递给 sink 一次");
+        assert_eq!(delivered[0].tokens.len(), 1);
+        assert_eq!(delivered[0].tokens[0].name, "TEST");
+    }
+
+    /// `collect_results_loop` 应该把经由 `results_bus` 广播的已批准结果
+    /// 汇总进周期摘要；摘要投递给 sink 时不应重复携带已经实时投递过的
+    /// token 明细，只反映本轮的消息统计
+    #[tokio::test]
+    async fn test_collect_results_loop_feeds_digest_without_duplicating_tokens() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let config = test_config();
+        let ai_service: Arc<dyn AIService> = Arc::new(FakeAIService {
+            result: relevant_result("TEST"),
+        });
+        let telegram_bot = Arc::new(TelegramBot::new(config.telegram.clone()));
+        let sink: Box<dyn Sink> = Box::new(FakeSink { received: Arc::clone(&received) });
+
+        let processor = Arc::new(MessageProcessor::new(config, ai_service, telegram_bot, vec![sink]));
+        processor.start().await.expect("启动处理器失败");
+
+        let message = Message::new(1, -100123, "TestChannel".to_string(), "hello".to_string(), 0);
+        processor.process_message(message).await.expect("发送消息失败");
+
+        // 等待批处理循环和 collect_results_loop 消费完这条消息
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        processor.force_summary().await.expect("生成摘要失败");
+
+        let delivered = received.lock().await;
+        assert_eq!(delivered.len(), 2, "应有一次实时信号投递 + 一次周期摘要投递");
+        assert_eq!(delivered[0].tokens.len(), 1, "实时信号应带完整 token 明细");
+        assert_eq!(delivered[1].tokens.len(), 0, "周期摘要不应重复携带已实时投递的 token");
+        assert_eq!(delivered[1].relevant_messages, 1);
+        assert_eq!(delivered[1].total_messages, 1);
+    }
 }
@@ -0,0 +1,53 @@
+pub mod telegram;
+pub mod webhook;
+
+use crate::ai::models::SummaryReport;
+use crate::config::SinkConfig;
+use crate::telegram::bot::TelegramBot;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// 信号投递目的地的统一接口
+///
+/// 每个 sink 独立负责把同一份 `SummaryReport` 送到自己的目的地（Telegram
+/// DM、外部 webhook……），`MessageProcessor` 并发调用所有已配置 sink 的
+/// `deliver`，单个 sink 失败只记录日志，不影响其余 sink 继续投递
+#[async_trait]
+pub trait Sink: Send + Sync {
+    /// 投递一份汇总报告
+    async fn deliver(&self, signal: &SummaryReport) -> Result<()>;
+
+    /// 健康检查，用于启动时确认 sink 大致可用
+    async fn health_check(&self) -> bool;
+
+    /// sink 名称，仅用于日志区分
+    fn name(&self) -> String;
+}
+
+/// Sink 工厂，根据配置创建对应的 sink 实例
+pub struct SinkFactory;
+
+impl SinkFactory {
+    /// 根据 `[[sinks]]` 配置创建所有 sink
+    ///
+    /// 未配置任何 sink 时退化为单个 telegram sink，与引入 sink 子系统
+    /// 之前“报告只转发到 `target_user`”的行为保持一致
+    pub fn create_all(configs: &[SinkConfig], telegram_bot: Arc<TelegramBot>) -> Result<Vec<Box<dyn Sink>>> {
+        if configs.is_empty() {
+            return Ok(vec![Box::new(telegram::TelegramSink::new(Arc::clone(&telegram_bot)))]);
+        }
+
+        let mut sinks: Vec<Box<dyn Sink>> = Vec::with_capacity(configs.len());
+        for config in configs {
+            let sink: Box<dyn Sink> = match config.kind.as_str() {
+                "telegram" => Box::new(telegram::TelegramSink::new(Arc::clone(&telegram_bot))),
+                "webhook" => Box::new(webhook::WebhookSink::new(config)?),
+                other => anyhow::bail!("不支持的 sink type: {}", other),
+            };
+            sinks.push(sink);
+        }
+
+        Ok(sinks)
+    }
+}
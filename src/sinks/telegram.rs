@@ -0,0 +1,33 @@
+use super::Sink;
+use crate::ai::models::SummaryReport;
+use crate::telegram::bot::TelegramBot;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// 把汇总报告转发到 `telegram.target_user` 的 sink，包装既有的
+/// `TelegramBot::send_message_raw`，是未配置 `[[sinks]]` 时的默认行为
+pub struct TelegramSink {
+    bot: Arc<TelegramBot>,
+}
+
+impl TelegramSink {
+    pub fn new(bot: Arc<TelegramBot>) -> Self {
+        Self { bot }
+    }
+}
+
+#[async_trait]
+impl Sink for TelegramSink {
+    async fn deliver(&self, signal: &SummaryReport) -> Result<()> {
+        self.bot.send_message_raw(&signal.format_full_report()).await
+    }
+
+    async fn health_check(&self) -> bool {
+        self.bot.health_check().await.unwrap_or(false)
+    }
+
+    fn name(&self) -> String {
+        "telegram".to_string()
+    }
+}
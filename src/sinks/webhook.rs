@@ -0,0 +1,83 @@
+use super::Sink;
+use crate::ai::models::SummaryReport;
+use crate::config::SinkConfig;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 把汇总报告以 JSON 形式 POST 到外部地址的 sink，供接入自建的通知/
+/// 自动化服务使用
+pub struct WebhookSink {
+    url: String,
+    bearer_token: Option<String>,
+    sha256_secret: Option<String>,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(config: &SinkConfig) -> Result<Self> {
+        let url = config.url.clone()
+            .ok_or_else(|| anyhow::anyhow!("webhook sink 缺少 url"))?;
+
+        Ok(Self {
+            url,
+            bearer_token: config.bearer_token.clone(),
+            sha256_secret: config.sha256_secret.clone(),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// 对请求体做 HMAC-SHA256 签名，返回十六进制字符串
+    fn sign(secret: &str, body: &[u8]) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+            .expect("HMAC 密钥可以是任意长度");
+        mac.update(body);
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Sink for WebhookSink {
+    async fn deliver(&self, signal: &SummaryReport) -> Result<()> {
+        let body = serde_json::to_vec(signal).context("序列化汇总报告失败")?;
+
+        let mut request = self.client
+            .post(&self.url)
+            .header("Content-Type", "application/json");
+
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        if let Some(secret) = &self.sha256_secret {
+            let signature = Self::sign(secret, &body);
+            request = request.header("X-Signal-Signature-256", format!("sha256={}", signature));
+        }
+
+        let response = request.body(body).send().await
+            .with_context(|| format!("投递 webhook 失败: {}", self.url))?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("webhook {} 返回非成功状态: {}", self.url, response.status());
+        }
+
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        // webhook 没有统一的健康检查接口，只要配置了合法的 URL 就视为可用，
+        // 真正的可达性在首次投递失败时通过日志暴露
+        !self.url.is_empty()
+    }
+
+    fn name(&self) -> String {
+        format!("webhook({})", self.url)
+    }
+}
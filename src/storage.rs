@@ -0,0 +1,333 @@
+//! 持久化存储子系统
+//!
+//! 将每一条收到的消息及其分析结果落盘到 SQLite（按 `channel_id` +
+//! `message_id` 去重），使 `SummaryReport` 能够跨重启、跨时间窗口聚合，
+//! 而不再局限于 `MessageProcessor` 内存中那一批尚未发送的结果。
+
+use crate::ai::models::{AnalysisResult, Message, SummaryReport, TokenInfo};
+use anyhow::{Context, Result};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::params;
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS messages (
+    channel_id INTEGER NOT NULL,
+    message_id INTEGER NOT NULL,
+    channel_name TEXT NOT NULL,
+    text TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    sender TEXT,
+    media_type TEXT,
+    PRIMARY KEY (channel_id, message_id)
+);
+
+CREATE TABLE IF NOT EXISTS analysis_results (
+    channel_id INTEGER NOT NULL,
+    message_id INTEGER NOT NULL,
+    is_relevant INTEGER NOT NULL,
+    token_name TEXT,
+    contract_address TEXT,
+    recommendation TEXT,
+    reason TEXT,
+    confidence REAL NOT NULL,
+    urgency INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    raw_response TEXT,
+    PRIMARY KEY (channel_id, message_id)
+);
+"#;
+
+/// 持久化存储句柄，内部持有连接池，可自由 `Clone`（克隆只复制池的 `Arc`）
+#[derive(Clone)]
+pub struct Storage {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl Storage {
+    /// 打开（或创建）指定路径的 SQLite 数据库并完成表结构初始化
+    pub fn open(db_path: &str) -> Result<Self> {
+        let manager = SqliteConnectionManager::file(db_path);
+        let pool = Pool::new(manager).context("创建 SQLite 连接池失败")?;
+
+        {
+            let conn = pool.get().context("获取数据库连接失败")?;
+            conn.execute_batch(SCHEMA).context("初始化数据库表结构失败")?;
+        }
+
+        info!("✓ 存储子系统已就绪: {}", db_path);
+        Ok(Self { pool })
+    }
+
+    /// 持久化一条消息及其分析结果（按 channel_id/message_id 去重）
+    pub fn record(&self, message: &Message, result: &AnalysisResult) -> Result<()> {
+        let conn = self.pool.get().context("获取数据库连接失败")?;
+
+        conn.execute(
+            "INSERT OR IGNORE INTO messages
+                (channel_id, message_id, channel_name, text, timestamp, sender, media_type)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                message.channel_id,
+                message.id,
+                message.channel_name,
+                message.text,
+                message.timestamp,
+                message.sender,
+                message.media_type,
+            ],
+        ).context("写入 messages 表失败")?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO analysis_results
+                (channel_id, message_id, is_relevant, token_name, contract_address,
+                 recommendation, reason, confidence, urgency, source, timestamp, raw_response)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                message.channel_id,
+                message.id,
+                result.is_relevant,
+                result.token_name,
+                result.contract_address,
+                result.recommendation,
+                result.reason,
+                result.confidence,
+                result.urgency,
+                result.source,
+                result.timestamp,
+                result.raw_response,
+            ],
+        ).context("写入 analysis_results 表失败")?;
+
+        debug!("已持久化消息 [{}] {}", message.channel_id, message.id);
+        Ok(())
+    }
+
+    /// 统计给定时间窗口内的消息总数与相关消息数
+    pub fn message_counts(&self, since: i64, until: i64) -> Result<(usize, usize)> {
+        let conn = self.pool.get().context("获取数据库连接失败")?;
+
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM analysis_results WHERE timestamp >= ?1 AND timestamp < ?2",
+            params![since, until],
+            |row| row.get(0),
+        ).context("统计消息总数失败")?;
+
+        let relevant: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM analysis_results WHERE is_relevant = 1 AND timestamp >= ?1 AND timestamp < ?2",
+            params![since, until],
+            |row| row.get(0),
+        ).context("统计相关消息数失败")?;
+
+        Ok((total as usize, relevant as usize))
+    }
+
+    /// 在给定时间窗口内按 `contract_address`（缺失时退化为 `token_name`）
+    /// 分组，聚合出每个 Token 的提及次数、来源、平均置信度和主要建议
+    pub fn token_summary(&self, since: i64, until: i64) -> Result<Vec<TokenInfo>> {
+        let conn = self.pool.get().context("获取数据库连接失败")?;
+
+        let mut stmt = conn.prepare(
+            "SELECT token_name, contract_address, source, recommendation, confidence, timestamp
+             FROM analysis_results
+             WHERE is_relevant = 1 AND timestamp >= ?1 AND timestamp < ?2
+               AND (token_name IS NOT NULL OR contract_address IS NOT NULL)
+             ORDER BY timestamp ASC",
+        ).context("准备 token_summary 查询失败")?;
+
+        struct Row {
+            token_name: Option<String>,
+            contract_address: Option<String>,
+            source: String,
+            recommendation: Option<String>,
+            confidence: f32,
+            timestamp: i64,
+        }
+
+        let rows = stmt.query_map(params![since, until], |row| {
+            Ok(Row {
+                token_name: row.get(0)?,
+                contract_address: row.get(1)?,
+                source: row.get(2)?,
+                recommendation: row.get(3)?,
+                confidence: row.get(4)?,
+                timestamp: row.get(5)?,
+            })
+        }).context("执行 token_summary 查询失败")?;
+
+        let mut groups: HashMap<String, Vec<Row>> = HashMap::new();
+        for row in rows {
+            let row = row.context("读取 token_summary 行失败")?;
+            let key = row.contract_address.clone()
+                .or_else(|| row.token_name.clone())
+                .expect("WHERE 子句已保证 token_name/contract_address 至少一个非空");
+            groups.entry(key).or_insert_with(Vec::new).push(row);
+        }
+
+        let mut tokens: Vec<TokenInfo> = groups.into_values().map(|rows| {
+            let mentions = rows.len() as i32;
+
+            let mut sources = Vec::new();
+            for row in &rows {
+                if !sources.contains(&row.source) {
+                    sources.push(row.source.clone());
+                }
+            }
+
+            let mut recommendation_counts: HashMap<String, usize> = HashMap::new();
+            let mut total_confidence = 0.0;
+            for row in &rows {
+                if let Some(rec) = &row.recommendation {
+                    *recommendation_counts.entry(rec.clone()).or_insert(0) += 1;
+                }
+                total_confidence += row.confidence;
+            }
+
+            let recommendation = recommendation_counts.into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(rec, _)| rec)
+                .unwrap_or_else(|| "观望".to_string());
+
+            TokenInfo {
+                name: rows[0].token_name.clone().unwrap_or_else(|| "未知".to_string()),
+                contract_address: rows[0].contract_address.clone(),
+                mentions,
+                sources,
+                recommendation,
+                avg_confidence: total_confidence / mentions as f32,
+                first_seen: rows.iter().map(|r| r.timestamp).min().unwrap_or(0),
+                last_seen: rows.iter().map(|r| r.timestamp).max().unwrap_or(0),
+            }
+        }).collect();
+
+        tokens.sort_by(|a, b| b.mentions.cmp(&a.mentions));
+        Ok(tokens)
+    }
+}
+
+impl SummaryReport {
+    /// 基于持久化存储，为指定时间窗口 `[since, until)` 构建汇总报告
+    ///
+    /// 与 `SummaryReport::new` 不同，这个构造函数不依赖调用方手头的
+    /// `&[AnalysisResult]`，而是直接查询存储层，因此可以用于定时
+    /// 日报/周报，也可以用于用户发起的历史查询。
+    pub fn for_window(storage: &Storage, since: i64, until: i64) -> Result<Self> {
+        let tokens = storage.token_summary(since, until)?;
+        let (total_messages, relevant_messages) = storage.message_counts(since, until)?;
+
+        Ok(Self {
+            tokens,
+            generated_at: chrono::Utc::now().timestamp(),
+            total_messages,
+            relevant_messages,
+            ai_usage: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// 测试用 SQLite 文件路径，带进程 id 避免并发测试互相干扰；
+    /// 用完即删
+    struct TempDb {
+        path: PathBuf,
+        storage: Storage,
+    }
+
+    impl TempDb {
+        fn new(label: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "tg-monitor-storage-test-{}-{}.sqlite",
+                label,
+                std::process::id()
+            ));
+            std::fs::remove_file(&path).ok();
+            let storage = Storage::open(path.to_str().unwrap()).unwrap();
+            Self { path, storage }
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            std::fs::remove_file(&self.path).ok();
+        }
+    }
+
+    fn record(storage: &Storage, channel_id: i64, message_id: i64, timestamp: i64, result: AnalysisResult) {
+        let message = Message {
+            id: message_id,
+            channel_id,
+            channel_name: "TestChannel".to_string(),
+            text: "测试消息".to_string(),
+            timestamp,
+            sender: None,
+            media_type: None,
+            media_data: None,
+        };
+        storage.record(&message, &result).unwrap();
+    }
+
+    fn relevant_result(token_name: &str, contract_address: &str, source: &str, recommendation: &str, confidence: f32, timestamp: i64) -> AnalysisResult {
+        AnalysisResult {
+            is_relevant: true,
+            token_name: Some(token_name.to_string()),
+            contract_address: Some(contract_address.to_string()),
+            chain: Some("evm".to_string()),
+            recommendation: Some(recommendation.to_string()),
+            reason: None,
+            confidence,
+            urgency: 3,
+            source: source.to_string(),
+            timestamp,
+            raw_response: None,
+        }
+    }
+
+    #[test]
+    fn test_message_counts_filters_by_window_and_relevance() {
+        let db = TempDb::new("message-counts");
+
+        record(&db.storage, -100, 1, 100, relevant_result("A", "0x1", "kimi", "买入", 0.9, 100));
+        record(&db.storage, -100, 2, 150, AnalysisResult { timestamp: 150, ..AnalysisResult::empty() });
+        record(&db.storage, -100, 3, 500, relevant_result("B", "0x2", "kimi", "卖出", 0.8, 500));
+
+        let (total, relevant) = db.storage.message_counts(0, 200).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(relevant, 1);
+    }
+
+    #[test]
+    fn test_token_summary_aggregates_mentions_sources_and_avg_confidence() {
+        let db = TempDb::new("token-summary");
+
+        record(&db.storage, -100, 1, 100, relevant_result("A", "0xabc", "kimi", "买入", 0.6, 100));
+        record(&db.storage, -100, 2, 150, relevant_result("A", "0xabc", "openai", "买入", 0.8, 150));
+
+        let tokens = db.storage.token_summary(0, 200).unwrap();
+        assert_eq!(tokens.len(), 1);
+
+        let token = &tokens[0];
+        assert_eq!(token.mentions, 2);
+        assert_eq!(token.sources, vec!["kimi".to_string(), "openai".to_string()]);
+        assert_eq!(token.recommendation, "买入");
+        assert!((token.avg_confidence - 0.7).abs() < 1e-6);
+        assert_eq!(token.first_seen, 100);
+        assert_eq!(token.last_seen, 150);
+    }
+
+    #[test]
+    fn test_token_summary_excludes_results_outside_window() {
+        let db = TempDb::new("token-summary-window");
+
+        record(&db.storage, -100, 1, 100, relevant_result("A", "0xabc", "kimi", "买入", 0.9, 100));
+
+        let tokens = db.storage.token_summary(200, 300).unwrap();
+        assert!(tokens.is_empty());
+    }
+}
@@ -1,85 +1,691 @@
 use crate::config::TelegramConfig;
+use crate::processor::MessageProcessor;
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// 单条命令执行时能访问的上下文：命令所在的 chat_id 和共享的 `MessageProcessor`
+struct CommandContext<'a> {
+    chat_id: i64,
+    processor: &'a Arc<MessageProcessor>,
+}
+
+/// 命令执行结果：要么回复一段文本，要么命令自己已经直接发送了消息
+/// （例如 `/remove` 先发一条带确认按钮的消息，不需要再额外回复一次）
+enum CommandOutcome {
+    Reply(String),
+    Handled,
+}
+
+/// Bot 斜杠命令的统一接口，每个命令各自实现 `name`/`execute`，
+/// `TelegramBot` 只维护一份注册表按名称匹配分发，新增命令不需要再碰
+/// 长轮询和解析逻辑
+#[async_trait]
+trait Command: Send + Sync {
+    /// 命令名（不含前导 `/`），用于匹配和 `setMyCommands` 注册
+    fn name(&self) -> &'static str;
+
+    /// `setMyCommands` 注册时展示的一句话说明（未知命令提示只列出命令名）
+    fn description(&self) -> &'static str;
+
+    /// 额外可匹配的别名（如 `channels` 对应 `/list`）
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// 执行命令；`bot` 供需要直接发消息的命令使用（如 `/remove` 的确认按钮）
+    async fn execute(&self, bot: &TelegramBot, ctx: &CommandContext<'_>, arg: &str) -> CommandOutcome;
+}
+
+/// 内置命令注册表，顺序即 `setMyCommands` 与未知命令提示里的展示顺序
+fn build_commands() -> Vec<Box<dyn Command>> {
+    vec![
+        Box::new(ChannelsCommand),
+        Box::new(AddCommand),
+        Box::new(RemoveCommand),
+        Box::new(CheckCommand),
+        Box::new(StatusCommand),
+        Box::new(SummaryCommand),
+    ]
+}
+
+struct ChannelsCommand;
+
+#[async_trait]
+impl Command for ChannelsCommand {
+    fn name(&self) -> &'static str {
+        "channels"
+    }
+
+    fn description(&self) -> &'static str {
+        "列出当前监控的频道"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["list"]
+    }
+
+    async fn execute(&self, _bot: &TelegramBot, ctx: &CommandContext<'_>, _arg: &str) -> CommandOutcome {
+        let reply = match ctx.processor.get_channels().await {
+            Ok(channels) if channels.is_empty() => "当前没有监控任何频道".to_string(),
+            Ok(channels) => {
+                let mut lines = vec![format!("共监控 {} 个频道：", channels.len())];
+                for channel in channels {
+                    lines.push(format!(
+                        "- {} ({})",
+                        channel.channel_id,
+                        channel.channel_name.unwrap_or_else(|| "未知".to_string())
+                    ));
+                }
+                lines.join("\n")
+            }
+            Err(e) => format!("获取频道列表失败: {}", e),
+        };
+        CommandOutcome::Reply(reply)
+    }
+}
+
+struct AddCommand;
+
+#[async_trait]
+impl Command for AddCommand {
+    fn name(&self) -> &'static str {
+        "add"
+    }
+
+    fn description(&self) -> &'static str {
+        "添加监控频道: /add <channel_id> [名称]"
+    }
+
+    async fn execute(&self, _bot: &TelegramBot, ctx: &CommandContext<'_>, arg: &str) -> CommandOutcome {
+        let mut parts = arg.trim().splitn(2, char::is_whitespace);
+        let reply = match parts.next().unwrap_or_default().parse::<i64>() {
+            Ok(channel_id) => {
+                let name = parts.next().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+                match ctx.processor.add_channel(channel_id, name.clone()).await {
+                    Ok(_) => match name {
+                        Some(name) => format!("✓ 已添加监控频道: {} ({})", channel_id, name),
+                        None => format!("✓ 已添加监控频道: {}", channel_id),
+                    },
+                    Err(e) => format!("添加频道失败: {}", e),
+                }
+            }
+            Err(_) => "用法: /add <channel_id> [名称]".to_string(),
+        };
+        CommandOutcome::Reply(reply)
+    }
+}
+
+struct RemoveCommand;
+
+#[async_trait]
+impl Command for RemoveCommand {
+    fn name(&self) -> &'static str {
+        "remove"
+    }
+
+    fn description(&self) -> &'static str {
+        "移除监控频道（需二次确认）: /remove <channel_id>"
+    }
+
+    async fn execute(&self, bot: &TelegramBot, ctx: &CommandContext<'_>, arg: &str) -> CommandOutcome {
+        match arg.trim().parse::<i64>() {
+            Ok(channel_id) => match bot.request_removal_confirmation(ctx.chat_id, channel_id).await {
+                Ok(_) => CommandOutcome::Handled,
+                Err(e) => CommandOutcome::Reply(format!("发送移除确认失败: {}", e)),
+            },
+            Err(_) => CommandOutcome::Reply("用法: /remove <channel_id>".to_string()),
+        }
+    }
+}
+
+struct CheckCommand;
+
+#[async_trait]
+impl Command for CheckCommand {
+    fn name(&self) -> &'static str {
+        "check"
+    }
+
+    fn description(&self) -> &'static str {
+        "检查频道是否在监控列表: /check <channel_id>"
+    }
+
+    async fn execute(&self, _bot: &TelegramBot, ctx: &CommandContext<'_>, arg: &str) -> CommandOutcome {
+        let reply = match arg.trim().parse::<i64>() {
+            Ok(channel_id) => match ctx.processor.has_channel(channel_id).await {
+                Ok(true) => format!("✓ 频道 {} 在监控列表中", channel_id),
+                Ok(false) => format!("频道 {} 不在监控列表中", channel_id),
+                Err(e) => format!("检查频道失败: {}", e),
+            },
+            Err(_) => "用法: /check <channel_id>".to_string(),
+        };
+        CommandOutcome::Reply(reply)
+    }
+}
+
+struct StatusCommand;
+
+#[async_trait]
+impl Command for StatusCommand {
+    fn name(&self) -> &'static str {
+        "status"
+    }
+
+    fn description(&self) -> &'static str {
+        "查看处理器运行状态"
+    }
+
+    async fn execute(&self, _bot: &TelegramBot, ctx: &CommandContext<'_>, _arg: &str) -> CommandOutcome {
+        let running = ctx.processor.is_running().await;
+        let queue_len = ctx.processor.queue_len().await;
+        CommandOutcome::Reply(format!(
+            "运行状态: {}\n待处理队列: {} 条消息",
+            if running { "运行中" } else { "已停止" },
+            queue_len
+        ))
+    }
+}
+
+struct SummaryCommand;
+
+#[async_trait]
+impl Command for SummaryCommand {
+    fn name(&self) -> &'static str {
+        "summary"
+    }
+
+    fn description(&self) -> &'static str {
+        "立即生成一次汇总报告"
+    }
+
+    async fn execute(&self, _bot: &TelegramBot, ctx: &CommandContext<'_>, _arg: &str) -> CommandOutcome {
+        let reply = match ctx.processor.force_summary().await {
+            Ok(_) => "✓ 已触发汇总报告".to_string(),
+            Err(e) => format!("生成汇总报告失败: {}", e),
+        };
+        CommandOutcome::Reply(reply)
+    }
+}
+
+/// 人工审批按钮对应的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Buy,
+    Watch,
+    Ignore,
+}
+
+impl Action {
+    fn to_byte(self) -> u8 {
+        match self {
+            Action::Buy => 0,
+            Action::Watch => 1,
+            Action::Ignore => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Action::Buy),
+            1 => Some(Action::Watch),
+            2 => Some(Action::Ignore),
+            _ => None,
+        }
+    }
+
+    /// 决定后回显在消息里的文案
+    fn outcome_label(self) -> &'static str {
+        match self {
+            Action::Buy => "🟢 已选择：买入",
+            Action::Watch => "🟡 已选择：观察",
+            Action::Ignore => "⚪ 已选择：忽略",
+        }
+    }
+}
 
 /// Telegram Bot API 客户端
 pub struct TelegramBot {
     token: String,
     target_user: i64,
+    /// 允许操作 Bot 命令的 chat id 允许列表，来自 `config.admin_chat_ids`
+    /// （留空时退化为仅 `target_user`）
+    admin_chat_ids: Vec<i64>,
+    /// 发送消息时使用的 `parse_mode`，来自 `config.parse_mode`；`None`
+    /// 表示按纯文本发送，不做任何富文本解析
+    parse_mode: Option<String>,
+    /// 是否在消息中禁用链接预览卡片，来自 `config.disable_web_page_preview`
+    disable_web_page_preview: bool,
+    /// 遇到 429/5xx 时的最大重试次数，来自 `config.max_retries`
+    max_retries: u32,
     client: reqwest::Client,
+    /// 专用于 `getUpdates` 长轮询的客户端，超时时间比 `client` 更长——
+    /// 长轮询请求的 `timeout=30` 参数本身就允许服务端挂起到 30 秒才
+    /// 返回，用通用的 30 秒客户端超时去套它会偶发地把正常的空轮询
+    /// response 打成超时错误
+    long_poll_client: reqwest::Client,
+    /// 等待人工决定的回调：uuid -> 一次性应答通道
+    pending: Arc<Mutex<HashMap<Uuid, oneshot::Sender<Action>>>>,
+    /// 等待二次确认的 `/remove` 请求：uuid -> 待移除的 channel_id
+    pending_removals: Arc<Mutex<HashMap<Uuid, i64>>>,
+    /// 命令注册表，按名称/别名匹配分发
+    commands: Vec<Box<dyn Command>>,
+    /// `getUpdates` 的 offset 游标，必须在 `update_loop` 的多次重入之间
+    /// 保持不变——`run_command_loop` 的重试包装层在任何瞬时网络错误
+    /// 后都会重新调用 `update_loop`，如果 offset 跟着函数调用重新清零，
+    /// 每次重连都会把 Telegram 尚未确认消费的整个更新积压重放一遍
+    /// （包括早已过期的 `/summary` 等命令）
+    offset: AtomicI64,
+}
+
+/// 持有一个 `pending` 条目的所有权，确保无论 `prompt_decision` 是正常
+/// 收到回调、出错，还是被外层 `tokio::time::timeout` 取消（future 被
+/// 直接 drop，不会走到任何 `Err`/`Ok` 分支），对应的 uuid 都会从
+/// `pending` 里移除——否则每一次超时都会在 HashMap 里永久泄漏一条记录
+struct PendingGuard {
+    pending: Arc<Mutex<HashMap<Uuid, oneshot::Sender<Action>>>>,
+    id: Uuid,
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        let pending = self.pending.clone();
+        let id = self.id;
+        tokio::spawn(async move {
+            pending.lock().await.remove(&id);
+        });
+    }
 }
 
 #[derive(Serialize)]
 struct SendMessageRequest<'a> {
     chat_id: i64,
     text: &'a str,
-    parse_mode: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parse_mode: Option<&'a str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    disable_web_page_preview: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_markup: Option<InlineKeyboardMarkup>,
+}
+
+#[derive(Serialize)]
+struct EditMessageTextRequest<'a> {
+    chat_id: i64,
+    message_id: i64,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parse_mode: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct AnswerCallbackQueryRequest<'a> {
+    callback_query_id: &'a str,
+}
+
+#[derive(Serialize)]
+struct InlineKeyboardMarkup {
+    inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
+}
+
+#[derive(Serialize)]
+struct InlineKeyboardButton {
+    text: String,
+    callback_data: String,
 }
 
 #[derive(Deserialize, Debug)]
 struct TelegramResponse {
     ok: bool,
     #[serde(default)]
+    error_code: Option<i64>,
+    #[serde(default)]
     description: Option<String>,
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    parameters: Option<ResponseParameters>,
+}
+
+/// `ok: false` 响应里附带的限流/迁移信息
+#[derive(Deserialize, Debug, Default)]
+struct ResponseParameters {
+    /// 触发 429 时，需要等待多少秒后才能重试
+    #[serde(default)]
+    retry_after: Option<i64>,
+    /// 目标群组升级为超级群组后的新 chat id，需要改用新 id 重发
+    #[serde(default)]
+    migrate_to_chat_id: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GetUpdatesResponse {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Update {
+    update_id: i64,
+    #[serde(default)]
+    callback_query: Option<CallbackQuery>,
+    #[serde(default)]
+    message: Option<IncomingMessage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CallbackQuery {
+    id: String,
+    #[serde(default)]
+    data: Option<String>,
+    message: Option<CallbackMessage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CallbackMessage {
+    message_id: i64,
+    chat: Chat,
+}
+
+#[derive(Deserialize, Debug)]
+struct Chat {
+    id: i64,
+}
+
+/// `getUpdates` 里的普通消息，仅用于解析 `from.id`、所在会话和命令文本
+#[derive(Deserialize, Debug)]
+struct IncomingMessage {
+    #[serde(default)]
+    text: Option<String>,
+    from: Option<FromUser>,
+    #[serde(default)]
+    chat: Option<Chat>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FromUser {
+    id: i64,
+}
+
+#[derive(Serialize)]
+struct SetMyCommandsRequest {
+    commands: Vec<BotCommand>,
+}
+
+#[derive(Serialize)]
+struct BotCommand {
+    command: String,
+    description: String,
 }
 
 impl TelegramBot {
+    /// 每次 `getUpdates` 请求携带的长轮询超时（秒），由 Telegram 服务端
+    /// 在这段时间内没有新更新时才返回空结果
+    const GET_UPDATES_POLL_SECONDS: u64 = 30;
+
     /// 创建 Bot 客户端
     pub fn new(config: TelegramConfig) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .expect("创建 HTTP 客户端失败");
+        let mut builder = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30));
+
+        let mut long_poll_builder = reqwest::Client::builder()
+            // 比服务端的长轮询超时多留 10 秒余量，避免客户端超时和服务端
+            // 长轮询超时互相竞争，把一次正常的空轮询打成超时错误
+            .timeout(std::time::Duration::from_secs(Self::GET_UPDATES_POLL_SECONDS + 10));
+
+        if let Some(proxy_url) = &config.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url).expect("Telegram 代理 URL 无效");
+            builder = builder.proxy(proxy.clone());
+            long_poll_builder = long_poll_builder.proxy(proxy);
+            info!("Telegram Bot 客户端已启用代理: {}", proxy_url);
+        }
+
+        let client = builder.build().expect("创建 HTTP 客户端失败");
+        let long_poll_client = long_poll_builder.build().expect("创建长轮询 HTTP 客户端失败");
+        let admin_chat_ids = config.admin_ids();
 
         Self {
             token: config.bot_token,
             target_user: config.target_user,
+            admin_chat_ids,
+            parse_mode: config.parse_mode,
+            disable_web_page_preview: config.disable_web_page_preview,
+            max_retries: config.max_retries,
             client,
+            long_poll_client,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            pending_removals: Arc::new(Mutex::new(HashMap::new())),
+            commands: build_commands(),
+            offset: AtomicI64::new(0),
         }
     }
 
-    /// 发送消息给用户
+    /// 发送消息给用户，`text` 是未经处理的原始内容
+    ///
+    /// 当 `parse_mode` 为 `MarkdownV2` 时会先用 [`escape_markdown_v2`] 整体
+    /// 转义，确保 AI 生成的 Token 名称、合约地址里混入的保留字符不会让
+    /// Telegram 拒收整条消息。已经按模板拼好 Markdown 标记的内容（例如
+    /// `ai::models` 里的汇总报告）请改用 [`TelegramBot::send_message_raw`]，
+    /// 否则模板本身的 `*`、`_` 等标记也会被转义掉
     pub async fn send_message(&self, text: &str) -> Result<()> {
-        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.token);
+        let escaped;
+        let text = if self.parse_mode.as_deref() == Some("MarkdownV2") {
+            escaped = escape_markdown_v2(text);
+            escaped.as_str()
+        } else {
+            text
+        };
 
+        info!("发送消息到用户 {} (长度: {} 字符)", self.target_user, text.len());
+        self.send_message_raw(text).await
+    }
+
+    /// 发送消息给用户，跳过 MarkdownV2 转义——用于已经按模板拼好富文本
+    /// 标记的预格式化内容
+    pub async fn send_message_raw(&self, text: &str) -> Result<()> {
         let request = SendMessageRequest {
             chat_id: self.target_user,
             text,
-            parse_mode: "Markdown",
+            parse_mode: self.parse_mode.as_deref(),
+            disable_web_page_preview: self.disable_web_page_preview,
+            reply_markup: None,
         };
 
-        info!("发送消息到用户 {} (长度: {} 字符)", self.target_user, text.len());
+        self.call("sendMessage", &request).await.map(|_| ())
+    }
+
+    /// 回复命令触发所在的具体会话，而不是固定的 `target_user`——管理员
+    /// 允许列表里可能有多个不同的 chat
+    async fn reply_to(&self, chat_id: i64, text: &str) -> Result<()> {
+        let request = SendMessageRequest {
+            chat_id,
+            text,
+            parse_mode: self.parse_mode.as_deref(),
+            disable_web_page_preview: self.disable_web_page_preview,
+            reply_markup: None,
+        };
+        self.call("sendMessage", &request).await.map(|_| ())
+    }
+
+    /// 以图片形式发送消息，保留图表/合约截图等文本转发会丢失的视觉信息
+    pub async fn send_photo(&self, caption: &str, image: &[u8]) -> Result<()> {
+        info!("发送图片到用户 {} ({} 字节)", self.target_user, image.len());
+
+        self.call_multipart("sendPhoto", caption, "photo", "photo.jpg", image).await
+    }
+
+    /// 以文件形式发送消息（非图片媒体，例如大图或其他附件）
+    pub async fn send_document(&self, caption: &str, document: &[u8], filename: &str) -> Result<()> {
+        info!("发送文件到用户 {} ({}, {} 字节)", self.target_user, filename, document.len());
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&request)
-            .send()
-            .await
-            .context("发送 Telegram 消息失败")?;
-
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .context("读取响应失败")?;
-
-        if status.is_success() {
-            // 解析响应
-            let result: TelegramResponse = serde_json::from_str(&body)
-                .context("解析 Telegram 响应失败")?;
+        self.call_multipart("sendDocument", caption, "document", filename, document).await
+    }
+
+    /// `sendPhoto`/`sendDocument` 共用的 multipart 请求封装
+    ///
+    /// 走与 [`Self::call`] 相同的 429/5xx 退避重试和 chat 迁移逻辑——
+    /// `reqwest::multipart::Form`/`Part` 不可克隆，重试时必须用原始
+    /// `bytes` 重新构建一份表单，因此这里没有直接复用 `call`，而是把
+    /// `bytes`/`filename` 而非预先构建好的 `Part` 传进来
+    async fn call_multipart(
+        &self,
+        method: &str,
+        caption: &str,
+        field_name: &'static str,
+        filename: &str,
+        bytes: &[u8],
+    ) -> Result<()> {
+        let url = format!("https://api.telegram.org/bot{}/{}", self.token, method);
+        let mut chat_id = self.target_user;
+        let mut backoff = Duration::from_secs(1);
+
+        for attempt in 0..=self.max_retries {
+            let part = reqwest::multipart::Part::bytes(bytes.to_vec()).file_name(filename.to_string());
+            let mut form = reqwest::multipart::Form::new()
+                .text("chat_id", chat_id.to_string())
+                .text("caption", caption.to_string())
+                .part(field_name, part);
+
+            if let Some(parse_mode) = &self.parse_mode {
+                form = form.text("parse_mode", parse_mode.clone());
+            }
+
+            let response = self
+                .client
+                .post(&url)
+                .multipart(form)
+                .send()
+                .await
+                .with_context(|| format!("调用 Telegram {} 失败", method))?;
+
+            let status = response.status();
+            let body = response.text().await.context("读取响应失败")?;
+
+            let can_retry = attempt < self.max_retries;
+
+            let result: TelegramResponse = match serde_json::from_str(&body) {
+                Ok(result) => result,
+                Err(e) => {
+                    if status.is_server_error() && can_retry {
+                        warn!("Telegram {} 返回非 JSON 的 {} 响应，{} 秒后重试", method, status, backoff.as_secs());
+                        tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                        continue;
+                    }
+                    return Err(e).with_context(|| format!("解析 Telegram {} 响应失败 (HTTP {})", method, status));
+                }
+            };
 
             if result.ok {
-                info!("✓ 消息发送成功");
-                Ok(())
-            } else {
-                error!("✗ Telegram API 错误: {:?}", result.description);
-                anyhow::bail!("Telegram API 错误: {:?}", result.description);
+                return Ok(());
+            }
+
+            if let Some(new_chat_id) = result.parameters.as_ref().and_then(|p| p.migrate_to_chat_id) {
+                warn!("chat 已迁移到超级群组 {}，改用新 chat id 重发 {}", new_chat_id, method);
+                chat_id = new_chat_id;
+                continue;
+            }
+
+            if status.as_u16() == 429 && can_retry {
+                let retry_after = result.parameters.as_ref().and_then(|p| p.retry_after).unwrap_or(1).max(0);
+                warn!("Telegram 限流 {}，{} 秒后重试", method, retry_after);
+                tokio::time::sleep(Duration::from_secs(retry_after as u64)).await;
+                continue;
+            }
+
+            if status.is_server_error() && can_retry {
+                warn!("Telegram {} 返回 {}，{} 秒后重试", method, status, backoff.as_secs());
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                continue;
+            }
+
+            error!("✗ Telegram API 错误: {:?} (HTTP {})", result.description, status);
+            anyhow::bail!("Telegram API 错误: {:?}", result.description);
+        }
+
+        anyhow::bail!("调用 Telegram {} 失败：已达到最大重试次数", method)
+    }
+
+    /// 发送带内联键盘的消息，每个按钮携带 `"<16字节uuid><1字节action>"` 的
+    /// `callback_data`（十六进制编码，避免原始字节不是合法 UTF-8）
+    ///
+    /// 返回发送成功后的消息 ID（用于之后编辑该消息展示最终选择结果）和
+    /// 本次注册进 callback_data 的 uuid
+    pub async fn send_message_with_buttons(
+        &self,
+        text: &str,
+        actions: &[(&str, Action)],
+    ) -> Result<(i64, Uuid)> {
+        let id = Uuid::new_v4();
+
+        let buttons = actions
+            .iter()
+            .map(|(label, action)| InlineKeyboardButton {
+                text: label.to_string(),
+                callback_data: encode_callback_data(&id, *action),
+            })
+            .collect();
+
+        let request = SendMessageRequest {
+            chat_id: self.target_user,
+            text,
+            parse_mode: self.parse_mode.as_deref(),
+            disable_web_page_preview: self.disable_web_page_preview,
+            reply_markup: Some(InlineKeyboardMarkup {
+                inline_keyboard: vec![buttons],
+            }),
+        };
+
+        info!("发送带审批按钮的消息到用户 {} (id: {})", self.target_user, id);
+        let result = self.call("sendMessage", &request).await?;
+
+        let message_id = result
+            .and_then(|v| v.get("message_id").and_then(|m| m.as_i64()))
+            .context("sendMessage 响应中缺少 message_id")?;
+
+        Ok((message_id, id))
+    }
+
+    /// 发送审批请求并等待人工在 Telegram 上点击按钮做出决定
+    ///
+    /// 注册一个 uuid，发送“买入/观察/忽略”内联键盘，然后挂起等待
+    /// [`run_command_loop`] 在收到对应 `callback_query` 时把决定
+    /// 通过 oneshot 通道送回来
+    ///
+    /// 调用方（如 [`crate::processor::MessageProcessor::request_human_approval`]）
+    /// 常常用 `tokio::time::timeout` 包裹这次调用；超时发生时这里的
+    /// `await` 会被直接取消而不会进入任何分支，因此 `pending` 条目的
+    /// 清理交给 [`PendingGuard`] 的 `Drop`，保证不管以哪种方式退出都会
+    /// 移除自己的 uuid
+    pub async fn prompt_decision(&self, text: &str) -> Result<Action> {
+        let (tx, rx) = oneshot::channel();
+
+        let actions: [(&str, Action); 3] = [
+            ("🟢 买入", Action::Buy),
+            ("🟡 观察", Action::Watch),
+            ("⚪ 忽略", Action::Ignore),
+        ];
+
+        let (_message_id, id) = self.send_message_with_buttons(text, &actions).await?;
+
+        self.pending.lock().await.insert(id, tx);
+        let _guard = PendingGuard { pending: self.pending.clone(), id };
+
+        match rx.await {
+            Ok(action) => Ok(action),
+            Err(_) => {
+                // 通道被丢弃（例如监听任务未启动）
+                anyhow::bail!("等待人工审批决定失败：回调通道被关闭");
             }
-        } else {
-            error!("✗ HTTP 请求失败: {} - {}", status, body);
-            anyhow::bail!("HTTP 请求失败: {} - {}", status, body);
         }
     }
 
@@ -103,4 +709,445 @@ impl TelegramBot {
             }
         }
     }
+
+    /// 在后台任务中启动唯一的 `getUpdates` 长轮询
+    ///
+    /// 同时承担两件事：分发审批按钮的 `callback_query`，以及把
+    /// `config.admin_chat_ids`（或退化后的 `target_user`）发来的
+    /// `/channels` `/add` 等命令路由到 `processor` 的对应方法。两者必须
+    /// 共用同一条轮询循环和同一个
+    /// offset 游标 —— `getUpdates` 是长连接、按 offset 确认消费的接口，
+    /// 多个循环各自维护 offset 会互相抢更新、互相漏更新。
+    ///
+    /// 与 [`crate::telegram::client::Client::start`] 一样按指数退避重连，
+    /// 失败不会让整个进程退出
+    pub fn run_command_loop(self: Arc<Self>, processor: Arc<MessageProcessor>) {
+        tokio::spawn(async move {
+            if let Err(e) = self.register_commands().await {
+                warn!("注册 Bot 命令失败（不影响长轮询）: {}", e);
+            }
+
+            let mut backoff = Duration::from_secs(1);
+            const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+            loop {
+                match self.update_loop(&processor).await {
+                    Ok(()) => backoff = Duration::from_secs(1),
+                    Err(e) => {
+                        error!("✗ Telegram 长轮询出错: {}，{} 秒后重试", e, backoff.as_secs());
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_BACKOFF);
+                    }
+                }
+            }
+        });
+    }
+
+    /// 向 Telegram 注册 `/channels` 等斜杠命令，使其出现在客户端的命令菜单里
+    async fn register_commands(&self) -> Result<()> {
+        let request = SetMyCommandsRequest {
+            commands: self
+                .commands
+                .iter()
+                .map(|cmd| BotCommand {
+                    command: cmd.name().to_string(),
+                    description: cmd.description().to_string(),
+                })
+                .collect(),
+        };
+
+        self.call("setMyCommands", &request).await.map(|_| ())
+    }
+
+    /// 长轮询 `getUpdates`，按类型把每个更新分发给审批回调或命令处理
+    async fn update_loop(&self, processor: &Arc<MessageProcessor>) -> Result<()> {
+        loop {
+            let offset = self.offset.load(Ordering::SeqCst);
+            let url = format!(
+                "https://api.telegram.org/bot{}/getUpdates?offset={}&timeout={}",
+                self.token, offset, Self::GET_UPDATES_POLL_SECONDS
+            );
+
+            let response = self
+                .long_poll_client
+                .get(&url)
+                .send()
+                .await
+                .context("长轮询 getUpdates 失败")?;
+
+            let body: GetUpdatesResponse = response
+                .json()
+                .await
+                .context("解析 getUpdates 响应失败")?;
+
+            if !body.ok {
+                anyhow::bail!("getUpdates 返回 ok=false");
+            }
+
+            for update in body.result {
+                self.offset.fetch_max(update.update_id + 1, Ordering::SeqCst);
+
+                if let Some(callback) = update.callback_query {
+                    if let Err(e) = self.handle_callback_query(callback, processor).await {
+                        warn!("处理审批回调失败: {}", e);
+                    }
+                }
+
+                if let Some(message) = update.message {
+                    if let Err(e) = self.handle_command_message(message, processor).await {
+                        warn!("处理命令失败: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 校验发信人在管理员允许列表内并解析出命令后分发到对应的处理器方法
+    async fn handle_command_message(
+        &self,
+        message: IncomingMessage,
+        processor: &Arc<MessageProcessor>,
+    ) -> Result<()> {
+        let Some(from_id) = message.from.as_ref().map(|f| f.id) else {
+            return Ok(());
+        };
+
+        if !self.admin_chat_ids.contains(&from_id) {
+            debug!("忽略来自非管理员 ({}) 的消息", from_id);
+            return Ok(());
+        }
+
+        let Some(text) = message.text.as_deref() else {
+            return Ok(());
+        };
+
+        if !text.starts_with('/') {
+            return Ok(());
+        }
+
+        // 回复发到命令所在的会话，而不是固定的 target_user——管理员
+        // 允许列表里可能有多个不同的 chat
+        let chat_id = message.chat.as_ref().map(|c| c.id).unwrap_or(from_id);
+
+        let mut parts = text.trim().splitn(2, char::is_whitespace);
+        // 命令可能带 "@botname" 后缀（群组中常见），去掉它只保留命令本身
+        let command = parts.next().unwrap_or_default().trim_start_matches('/');
+        let command = command.split('@').next().unwrap_or(command);
+        let arg = parts.next().map(str::trim).unwrap_or_default();
+
+        let reply = self.dispatch_command(chat_id, command, arg, processor).await;
+
+        // "remove" 分支在等待按钮确认前会自行发送消息，用空字符串表示
+        // 不需要再发一条额外的回复
+        if reply.is_empty() {
+            return Ok(());
+        }
+
+        self.reply_to(chat_id, &reply).await
+    }
+
+    /// 执行单个命令并返回要回复的文本（空字符串表示命令已自行发送过回复）
+    async fn dispatch_command(
+        &self,
+        chat_id: i64,
+        command: &str,
+        arg: &str,
+        processor: &Arc<MessageProcessor>,
+    ) -> String {
+        let matched = self
+            .commands
+            .iter()
+            .find(|cmd| cmd.name() == command || cmd.aliases().contains(&command));
+
+        let Some(matched) = matched else {
+            return format!(
+                "未知命令: /{}\n可用命令: {}",
+                command,
+                self.commands
+                    .iter()
+                    .map(|cmd| format!("/{}", cmd.name()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        };
+
+        let ctx = CommandContext { chat_id, processor };
+        match matched.execute(self, &ctx, arg).await {
+            CommandOutcome::Reply(text) => text,
+            CommandOutcome::Handled => String::new(),
+        }
+    }
+
+    /// 解析单个 `callback_query`，按前缀区分是交易决策还是 `/remove` 二次
+    /// 确认，分别路由到各自的处理逻辑
+    async fn handle_callback_query(
+        &self,
+        callback: CallbackQuery,
+        processor: &Arc<MessageProcessor>,
+    ) -> Result<()> {
+        let data = callback.data.clone().unwrap_or_default();
+
+        if let Some(id_str) = data.strip_prefix("rmyes:") {
+            return self.handle_removal_callback(callback, id_str, true, processor).await;
+        }
+        if let Some(id_str) = data.strip_prefix("rmno:") {
+            return self.handle_removal_callback(callback, id_str, false, processor).await;
+        }
+
+        let Some((id, action)) = decode_callback_data(&data) else {
+            debug!("忽略非审批相关的回调: {}", data);
+            return Ok(());
+        };
+
+        let sender = self.pending.lock().await.remove(&id);
+
+        if let Some(sender) = sender {
+            let _ = sender.send(action);
+        } else {
+            debug!("回调 {} 没有对应的等待者（可能已超时或重复点击）", id);
+        }
+
+        self.answer_callback_query(&callback.id).await?;
+
+        if let Some(message) = callback.message {
+            self.edit_message_text(message.chat.id, message.message_id, action.outcome_label())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 发送“确认移除频道 X？”的内联键盘，等待管理员点击后才真正调用
+    /// `processor.remove_channel`——`/remove` 一旦生效就要重新找回
+    /// channel_id 才能补救，误触代价比多一次确认高
+    async fn request_removal_confirmation(&self, chat_id: i64, channel_id: i64) -> Result<()> {
+        let id = Uuid::new_v4();
+        self.pending_removals.lock().await.insert(id, channel_id);
+
+        let text = format!("确认要移除监控频道 {} 吗？", channel_id);
+        let request = SendMessageRequest {
+            chat_id,
+            text: &text,
+            parse_mode: self.parse_mode.as_deref(),
+            disable_web_page_preview: self.disable_web_page_preview,
+            reply_markup: Some(InlineKeyboardMarkup {
+                inline_keyboard: vec![vec![
+                    InlineKeyboardButton {
+                        text: "✅ 确认移除".to_string(),
+                        callback_data: format!("rmyes:{}", id),
+                    },
+                    InlineKeyboardButton {
+                        text: "❌ 取消".to_string(),
+                        callback_data: format!("rmno:{}", id),
+                    },
+                ]],
+            }),
+        };
+
+        self.call("sendMessage", &request).await.map(|_| ())
+    }
+
+    /// 解析 `/remove` 确认按钮的回调，确认时调用 `remove_channel`，
+    /// 取消或请求已过期时只更新提示文案
+    async fn handle_removal_callback(
+        &self,
+        callback: CallbackQuery,
+        id_str: &str,
+        confirmed: bool,
+        processor: &Arc<MessageProcessor>,
+    ) -> Result<()> {
+        let Ok(id) = Uuid::parse_str(id_str) else {
+            debug!("忽略格式非法的移除确认回调: {}", id_str);
+            return Ok(());
+        };
+
+        let channel_id = self.pending_removals.lock().await.remove(&id);
+
+        self.answer_callback_query(&callback.id).await?;
+
+        let outcome = match channel_id {
+            None => "该确认请求已失效（可能已处理或过期）".to_string(),
+            Some(_) if !confirmed => "已取消".to_string(),
+            Some(channel_id) => match processor.remove_channel(channel_id).await {
+                Ok(_) => format!("✓ 已移除监控频道: {}", channel_id),
+                Err(e) => format!("移除频道失败: {}", e),
+            },
+        };
+
+        if let Some(message) = callback.message {
+            self.edit_message_text(message.chat.id, message.message_id, &outcome).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn answer_callback_query(&self, callback_query_id: &str) -> Result<()> {
+        let request = AnswerCallbackQueryRequest { callback_query_id };
+        self.call("answerCallbackQuery", &request).await.map(|_| ())
+    }
+
+    async fn edit_message_text(&self, chat_id: i64, message_id: i64, text: &str) -> Result<()> {
+        let request = EditMessageTextRequest {
+            chat_id,
+            message_id,
+            text,
+            parse_mode: self.parse_mode.as_deref(),
+        };
+        self.call("editMessageText", &request).await.map(|_| ())
+    }
+
+    /// 统一的 Telegram Bot API 调用封装
+    ///
+    /// 遇到 429 按响应里的 `retry_after` 精确等待后重试；遇到 5xx 按
+    /// 1s/2s/4s... 指数退避重试；遇到 `migrate_to_chat_id`（群组升级为
+    /// 超级群组）则把请求里的 `chat_id` 换成新 id 透明重发。三者都受
+    /// `max_retries` 封顶，避免批量转发信号时一次限流卡死整条长轮询
+    async fn call<T: Serialize>(&self, method: &str, request: &T) -> Result<Option<serde_json::Value>> {
+        let url = format!("https://api.telegram.org/bot{}/{}", self.token, method);
+        let mut payload = serde_json::to_value(request).context("序列化请求失败")?;
+        let mut backoff = Duration::from_secs(1);
+
+        for attempt in 0..=self.max_retries {
+            let response = self
+                .client
+                .post(&url)
+                .json(&payload)
+                .send()
+                .await
+                .with_context(|| format!("调用 Telegram {} 失败", method))?;
+
+            let status = response.status();
+            let body = response.text().await.context("读取响应失败")?;
+
+            let can_retry = attempt < self.max_retries;
+
+            // 代理/网关在 5xx 时经常返回非 JSON 的错误页面而不是
+            // Telegram 的 JSON 响应体；先按状态码决定是否退避重试，
+            // 避免这类响应在 JSON 解析上 `?` 掉，永远走不到下面的
+            // 5xx 重试分支
+            let result: TelegramResponse = match serde_json::from_str(&body) {
+                Ok(result) => result,
+                Err(e) => {
+                    if status.is_server_error() && can_retry {
+                        warn!("Telegram {} 返回非 JSON 的 {} 响应，{} 秒后重试", method, status, backoff.as_secs());
+                        tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                        continue;
+                    }
+                    return Err(e).with_context(|| format!("解析 Telegram {} 响应失败 (HTTP {})", method, status));
+                }
+            };
+
+            if result.ok {
+                return Ok(result.result);
+            }
+
+            if let Some(new_chat_id) = result.parameters.as_ref().and_then(|p| p.migrate_to_chat_id) {
+                warn!("chat 已迁移到超级群组 {}，改用新 chat id 重发 {}", new_chat_id, method);
+                if let Some(obj) = payload.as_object_mut() {
+                    obj.insert("chat_id".to_string(), serde_json::json!(new_chat_id));
+                }
+                continue;
+            }
+
+            if status.as_u16() == 429 && can_retry {
+                let retry_after = result.parameters.as_ref().and_then(|p| p.retry_after).unwrap_or(1).max(0);
+                warn!("Telegram 限流 {}，{} 秒后重试", method, retry_after);
+                tokio::time::sleep(Duration::from_secs(retry_after as u64)).await;
+                continue;
+            }
+
+            if status.is_server_error() && can_retry {
+                warn!("Telegram {} 返回 {}，{} 秒后重试", method, status, backoff.as_secs());
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                continue;
+            }
+
+            error!("✗ Telegram API 错误: {:?} (HTTP {})", result.description, status);
+            anyhow::bail!("Telegram API 错误: {:?}", result.description);
+        }
+
+        anyhow::bail!("调用 Telegram {} 失败：已达到最大重试次数", method)
+    }
+}
+
+/// 把 uuid + action 编码为十六进制 `callback_data`（最长 64 字节，17 字节原文完全够用）
+fn encode_callback_data(id: &Uuid, action: Action) -> String {
+    let mut bytes = [0u8; 17];
+    bytes[..16].copy_from_slice(id.as_bytes());
+    bytes[16] = action.to_byte();
+
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 解析 `callback_data`：取前 16 字节作为 uuid，最后 1 字节作为 action
+fn decode_callback_data(data: &str) -> Option<(Uuid, Action)> {
+    if data.len() != 34 {
+        return None;
+    }
+
+    let mut bytes = [0u8; 17];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&data[i * 2..i * 2 + 2], 16).ok()?;
+    }
+
+    let id = Uuid::from_slice(&bytes[..16]).ok()?;
+    let action = Action::from_byte(bytes[16])?;
+    Some((id, action))
+}
+
+/// 按 Telegram MarkdownV2 的规则转义保留字符，供拼接 AI 生成的 Token
+/// 名称、合约地址等不受信任的字段时使用——否则混入的 `.`、`-` 等符号会让
+/// Telegram 把整条消息当作格式错误拒收
+///
+/// `pub(crate)`：除了本文件的 [`TelegramBot::send_message`]，
+/// `MessageProcessor::format_approval_prompt` 也需要
+/// 在拼接人工审批提示时对插值字段单独转义
+pub(crate) fn escape_markdown_v2(text: &str) -> String {
+    const RESERVED: &[char] = &[
+        '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!',
+        '\\',
+    ];
+
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if RESERVED.contains(&ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_callback_data_roundtrip() {
+        let id = Uuid::new_v4();
+        let encoded = encode_callback_data(&id, Action::Buy);
+        let (decoded_id, decoded_action) = decode_callback_data(&encoded).unwrap();
+
+        assert_eq!(decoded_id, id);
+        assert_eq!(decoded_action, Action::Buy);
+    }
+
+    #[test]
+    fn test_decode_callback_data_rejects_garbage() {
+        assert!(decode_callback_data("not-hex").is_none());
+        assert!(decode_callback_data("ab").is_none()); // too short
+    }
+
+    #[test]
+    fn test_escape_markdown_v2_escapes_reserved_chars() {
+        let escaped = escape_markdown_v2("PEPE-2.0 (v2) [100%+]");
+        assert_eq!(escaped, "PEPE\\-2\\.0 \\(v2\\) \\[100%\\+\\]");
+    }
+
+    #[test]
+    fn test_escape_markdown_v2_leaves_plain_text_untouched() {
+        assert_eq!(escape_markdown_v2("hello world"), "hello world");
+    }
 }
@@ -1,21 +1,30 @@
-use crate::processor::MessageProcessor;
+use crate::ai::models::Message as AppMessage;
 use crate::config::TelegramConfig;
-use anyhow::{Result, Context};
-use tracing::{error, info, warn, debug};
+use crate::processor::MessageProcessor;
+use anyhow::{Context, Result};
+use grammers_client::{Client as GrammersClient, Config as GrammersClientConfig, InitParams, Update};
+use grammers_session::Session;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
 
-/// Telegram 客户端
+/// 原生 MTProto Telegram 客户端
 ///
-/// TODO: 需要集成实际的 Telegram 客户端库
-/// 当前使用模拟实现，用于演示架构
+/// 直接以用户账号连接 Telegram（而不是依赖 Python 监控器把消息
+/// HTTP POST 过来），订阅配置中的频道，把收到的更新转换成现有的
+/// `Message` 结构并喂给同一条 `MessageProcessor::process_message`
+/// 处理路径。HTTP 入口（`receive_message`）保持不变，两种接入方式
+/// 通过 `telegram.mtproto_ingestion_enabled` 配置项并存。
 pub struct Client {
     config: TelegramConfig,
-    processor: MessageProcessor,
+    processor: Arc<MessageProcessor>,
     is_connected: bool,
 }
 
 impl Client {
     /// 创建新的 Telegram 客户端
-    pub fn new(config: TelegramConfig, processor: MessageProcessor) -> Self {
+    pub fn new(config: TelegramConfig, processor: Arc<MessageProcessor>) -> Self {
         Self {
             config,
             processor,
@@ -23,51 +32,168 @@ impl Client {
         }
     }
 
-    /// 启动客户端并连接 Telegram
+    /// 启动客户端并保持连接，断线时按指数退避自动重连
     pub async fn start(&mut self) -> Result<()> {
-        info!("启动 Telegram 客户端...");
+        info!("启动 Telegram MTProto 客户端...");
         info!("API ID: {}", self.config.api_id);
         info!("会话文件: {}", self.config.session_file);
         info!("监控 {} 个频道", self.config.source_channels.len());
 
-        // TODO: 实际实现中应该：
-        // 1. 使用 grammers-client 或类似库连接 Telegram
-        // 2. 登录或加载会话
-        // 3. 订阅频道消息更新
-        // 4. 设置消息处理器
+        let mut backoff = Duration::from_secs(1);
+        const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+        loop {
+            match self.connect_and_listen().await {
+                Ok(()) => {
+                    info!("Telegram 客户端正常退出");
+                    break;
+                }
+                Err(e) => {
+                    self.is_connected = false;
+                    error!("✗ Telegram 连接中断: {}，{} 秒后重连", e, backoff.as_secs());
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 连接、登录（如有必要）并循环拉取更新，直到连接断开
+    async fn connect_and_listen(&mut self) -> Result<()> {
+        let session = Session::load_file_or_create(&self.config.session_file)
+            .context("加载/创建会话文件失败")?;
+
+        let client = GrammersClient::connect(GrammersClientConfig {
+            session,
+            api_id: self.config.api_id,
+            api_hash: self.config.api_hash.clone(),
+            params: InitParams::default(),
+        })
+        .await
+        .context("连接 Telegram 失败")?;
+
+        if !client.is_authorized().await.context("检查登录状态失败")? {
+            info!("会话未登录，开始交互式登录流程（仅需一次，之后复用会话文件）");
+            self.interactive_login(&client).await?;
+        }
+
+        client
+            .session()
+            .save_to_file(&self.config.session_file)
+            .context("保存会话文件失败")?;
 
-        // 模拟连接成功
         self.is_connected = true;
-        info!("✓ 成功连接 Telegram");
+        info!("✓ 成功连接 Telegram（MTProto）");
 
-        // 模拟消息接收（在真实环境中，这应该由 Telegram 库的回调触发）
-        self.start_message_loop().await?;
+        self.resolve_monitored_channels(&client).await;
 
-        Ok(())
+        loop {
+            tokio::select! {
+                update = client.next_update() => {
+                    let Some(update) = update.context("获取更新失败")? else {
+                        continue;
+                    };
+
+                    if let Err(e) = self.handle_update(update).await {
+                        warn!("处理 Telegram 更新失败: {}", e);
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    info!("收到 Ctrl-C，正在优雅停止 Telegram 客户端...");
+                    self.stop().await?;
+                    return Ok(());
+                }
+            }
+        }
     }
 
-    /// 启动消息循环（模拟）
-    async fn start_message_loop(&self) -> Result<()> {
-        info!("开始消息循环...");
-        info!("");
-        info!("==============================================");
-        info!("   注意：当前是演示版本");
-        info!("   需要集成实际的 Telegram 客户端库");
-        info!("   建议使用: grammers-client");
-        info!("==============================================");
-        info!("");
-        info!("消息处理器已就绪，等待传入消息...");
-
-        // TODO: 真实实现中，这里应该：
-        // 1. 创建一个 tokio::select! 循环
-        // 2. 等待 Telegram 更新
-        // 3. 调用 processor.process_message() 处理消息
-        // 4. 等待控制信号（如 Ctrl+C）
-
-        // 暂时阻塞以保持程序运行
-        tokio::signal::ctrl_c().await?;
+    /// 启动时解析并打印每个配置的监控频道，便于确认账号确实能看到它们
+    async fn resolve_monitored_channels(&self, client: &GrammersClient) {
+        let mut dialogs = client.iter_dialogs();
+        let mut resolved: std::collections::HashSet<i64> = std::collections::HashSet::new();
 
-        Ok(())
+        while let Ok(Some(dialog)) = dialogs.next().await {
+            let chat = dialog.chat();
+            if self.config.source_channels.contains(&chat.id()) {
+                resolved.insert(chat.id());
+                info!("✓ 已确认监控频道: {} ({})", chat.id(), chat.name().unwrap_or_default());
+            }
+        }
+
+        for channel_id in &self.config.source_channels {
+            if !resolved.contains(channel_id) {
+                warn!("⚠️  未能在当前账号的对话列表中找到频道 {}，请确认账号已加入该频道", channel_id);
+            }
+        }
+    }
+
+    /// 交互式登录：手机号 + 验证码，必要时再要求两步验证密码
+    ///
+    /// 只在会话文件不包含有效授权时才会走到这里；登录成功后的会话
+    /// 会被写回 `session_file`，后续重启直接复用，不会再次提示。
+    async fn interactive_login(&self, client: &GrammersClient) -> Result<()> {
+        let phone = prompt("请输入手机号（含国家代码，如 +8613800138000）: ")?;
+        let login_token = client
+            .request_login_code(&phone)
+            .await
+            .context("请求登录验证码失败")?;
+
+        let code = prompt("请输入收到的验证码: ")?;
+
+        match client.sign_in(&login_token, &code).await {
+            Ok(_) => {
+                info!("✓ 登录成功");
+                Ok(())
+            }
+            Err(grammers_client::SignInError::PasswordRequired(password_token)) => {
+                let password = prompt("该账号已开启两步验证，请输入密码: ")?;
+                client
+                    .check_password(password_token, password.trim())
+                    .await
+                    .context("两步验证密码错误")?;
+                info!("✓ 登录成功（两步验证）");
+                Ok(())
+            }
+            Err(e) => Err(anyhow::anyhow!("登录失败: {}", e)),
+        }
+    }
+
+    /// 将一条 Telegram 更新转换为内部 `Message` 并送入处理管道
+    async fn handle_update(&self, update: Update) -> Result<()> {
+        let Update::NewMessage(message) = update else {
+            return Ok(());
+        };
+
+        if message.outgoing() {
+            return Ok(());
+        }
+
+        let chat = message.chat();
+        let channel_id = chat.id();
+
+        // 按配置的频道列表过滤，未监控的频道直接丢弃
+        if !is_monitored_channel(&self.config.source_channels, channel_id) {
+            debug!("忽略未监控频道 {} 的消息", channel_id);
+            return Ok(());
+        }
+
+        let app_message = AppMessage {
+            id: message.id() as i64,
+            channel_id,
+            channel_name: chat.name().unwrap_or_default().to_string(),
+            text: message.text().to_string(),
+            timestamp: message.date().timestamp(),
+            sender: message.sender().and_then(|s| s.username().map(|u| u.to_string())),
+            media_type: message.media().map(|media| media_type_tag(&media).to_string()),
+            // MTProto 入口暂不下载媒体原始字节，只打标签；需要转发图片时
+            // 走 HTTP 入口（会携带 base64 负载）
+            media_data: None,
+        };
+
+        debug!("收到 MTProto 消息: {}", app_message.summary());
+        self.processor.process_message(app_message).await
     }
 
     /// 停止客户端
@@ -83,48 +209,70 @@ impl Client {
     }
 }
 
+/// 判断某个频道是否在监控列表中；`source_channels` 留空时视为不过滤
+/// 任何频道（实践中 `Config::validate` 已强制要求至少配置一个，这里是
+/// 对该不变量被打破时的保守兜底）
+fn is_monitored_channel(source_channels: &[i64], channel_id: i64) -> bool {
+    source_channels.is_empty() || source_channels.contains(&channel_id)
+}
+
+/// 根据 Telegram 媒体类型打上一个简短标签，写入 `Message::media_type`
+fn media_type_tag(media: &grammers_client::types::Media) -> &'static str {
+    use grammers_client::types::Media;
+
+    match media {
+        Media::Photo(_) => "photo",
+        Media::Document(_) => "document",
+        Media::Sticker(_) => "sticker",
+        Media::Contact(_) => "contact",
+        Media::Poll(_) => "poll",
+        Media::Geo(_) => "geo",
+        _ => "other",
+    }
+}
+
+/// 从标准输入读取一行（用于一次性的手机号/验证码/密码输入）
+fn prompt(label: &str) -> Result<String> {
+    print!("{}", label);
+    io::stdout().flush().context("刷新标准输出失败")?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).context("读取标准输入失败")?;
+    Ok(input.trim().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[tokio::test]
-    async fn test_client_creation() {
+    #[test]
+    fn test_client_config_fields() {
         let config = TelegramConfig {
             api_id: 12345,
             api_hash: "test_hash".to_string(),
             session_file: "test.session".to_string(),
             source_channels: vec![-1001234567890],
             target_user: 123456789,
+            bot_token: "test_bot_token".to_string(),
+            mtproto_ingestion_enabled: true,
+            proxy: None,
+            admin_chat_ids: Vec::new(),
+            parse_mode: None,
+            disable_web_page_preview: false,
+            max_retries: 3,
         };
 
-        let processor = MessageProcessor::new(
-            crate::config::Config {
-                telegram: config.clone(),
-                ai: crate::config::AIConfig {
-                    provider: "kimi".to_string(),
-                    timeout_seconds: 60,
-                    max_retries: 3,
-                    prompt_template: "Test".to_string(),
-                    ollama: None,
-                    kimi: Some(crate::config::KimiConfig {
-                        api_key: "test".to_string(),
-                        model: "moonshot-v1-8k".to_string(),
-                        base_url: "https://api.moonshot.cn/v1".to_string(),
-                    }),
-                    openai: None,
-                },
-                processing: crate::config::ProcessingConfig {
-                    batch_size: 10,
-                    batch_timeout_seconds: 300,
-                    min_confidence: 0.7,
-                    keywords: vec![],
-                },
-            },
-            // 这里需要一个 mock 的 AI service
-            unimplemented!("Test AI service needed"),
-        );
-
-        let client = Client::new(config, processor);
-        assert!(!client.is_connected());
+        // 构造 MessageProcessor 需要一个真正的 AI 服务和 Telegram Bot，
+        // 所以这里不构造 Client，只验证传给它的 TelegramConfig 字段
+        assert_eq!(config.source_channels.len(), 1);
+        assert!(!config.api_hash.is_empty());
+    }
+
+    #[test]
+    fn test_is_monitored_channel_filters_by_source_channels() {
+        let source_channels = vec![-1001234567890, -1009876543210];
+
+        assert!(is_monitored_channel(&source_channels, -1001234567890));
+        assert!(!is_monitored_channel(&source_channels, -1005555555555));
     }
 }
@@ -13,9 +13,11 @@ mod integration_tests {
     use super::*;
     use tg_meme_token_monitor::{
         ai::AIServiceFactory,
-        config::{AIConfig, Config, ProcessingConfig, TelegramConfig, HttpConfig, KimiConfig},
+        config::{AIConfig, Config, ProcessingConfig, TelegramConfig, KimiConfig},
         http::handler::{ApiResponse, ReceiveMessageRequest},
         processor::MessageProcessor,
+        sinks::SinkFactory,
+        telegram::bot::TelegramBot,
     };
     use axum::{body::Body, http::{Request, StatusCode}, Router};
 
@@ -27,6 +29,8 @@ mod integration_tests {
             text: "新币发布 TESTTOKEN 合约地址 0x1234567890abcdef 即将起飞".to_string(),
             timestamp: chrono::Utc::now().timestamp(),
             sender: Some("TestUser".to_string()),
+            media_type: None,
+            media_base64: None,
         }
     }
 
@@ -34,7 +38,6 @@ mod integration_tests {
     #[tokio::test]
     async fn test_config_loading() {
         let config = Config::load("config.toml").expect("Should load config");
-        assert_eq!(config.http.port, 8080);
         assert_eq!(config.ai.provider, "kimi");
         assert!(config.processing.batch_size > 0);
     }
@@ -187,8 +190,6 @@ mod integration_tests {
     // 测试消息通道和处理流程
     #[tokio::test]
     async fn test_message_processing_pipeline() {
-        use tg_meme_token_monitor::telegram::bot::TelegramBot;
-
         // 加载配置
         let config = Config::load("config.toml").unwrap();
 
@@ -196,10 +197,13 @@ mod integration_tests {
         let ai_service = AIServiceFactory::create(&config.ai).unwrap();
 
         // 创建 Telegram Bot
-        let _bot = TelegramBot::new(config.telegram.clone());
+        let bot = Arc::new(TelegramBot::new(config.telegram.clone()));
+
+        // 创建输出 sink（未配置 [[sinks]] 时退化为单个 telegram sink）
+        let sinks = SinkFactory::create_all(&config.sinks, Arc::clone(&bot)).unwrap();
 
         // 创建消息处理器
-        let processor = Arc::new(MessageProcessor::new(config.clone(), ai_service.into()));
+        let processor = Arc::new(MessageProcessor::new(config.clone(), ai_service.into(), bot, sinks));
         processor.start().await.expect("Failed to start processor");
 
         // 创建测试消息
@@ -211,6 +215,7 @@ mod integration_tests {
             timestamp: chrono::Utc::now().timestamp(),
             sender: Some("TestUser".to_string()),
             media_type: None,
+            media_data: None,
         };
 
         // 发送消息
@@ -231,11 +236,18 @@ mod integration_tests {
         // 加载测试配置
         let config = Config {
             telegram: TelegramConfig {
+                api_id: 1,
+                api_hash: "test-api-hash".to_string(),
+                session_file: "test-session".to_string(),
+                source_channels: vec![-1001234567890],
                 target_user: 8030185949,
                 bot_token: "TEST_BOT_TOKEN".to_string(),
-            },
-            http: HttpConfig {
-                port: 8080,
+                mtproto_ingestion_enabled: false,
+                proxy: None,
+                admin_chat_ids: vec![],
+                parse_mode: None,
+                disable_web_page_preview: false,
+                max_retries: 3,
             },
             processing: ProcessingConfig {
                 batch_size: 10,
@@ -245,6 +257,9 @@ mod integration_tests {
                     "token".to_string(),
                     "合约地址".to_string(),
                 ],
+                human_approval_threshold: None,
+                channels_store: None,
+                human_approval_timeout_seconds: 300,
             },
             ai: AIConfig {
                 provider: "kimi".to_string(),
@@ -255,19 +270,34 @@ mod integration_tests {
                     api_key: "TEST_API_KEY".to_string(),
                     model: "moonshot-v1-8k".to_string(),
                     base_url: "https://api.moonshot.cn/v1".to_string(),
+                    input_price_per_1k: 0.0,
+                    output_price_per_1k: 0.0,
                 }),
                 ollama: None,
                 openai: None,
+                ensemble: None,
+                proxy: None,
             },
+            storage: None,
+            sinks: vec![],
         };
 
         // 创建 AI 服务
         let ai_service = AIServiceFactory::create(&config.ai).expect("Should create AI service");
 
+        // 创建 Telegram Bot
+        let bot = Arc::new(TelegramBot::new(config.telegram.clone()));
+
+        // 创建输出 sink（测试配置未声明 [[sinks]]，退化为单个 telegram sink）
+        let sinks = SinkFactory::create_all(&config.sinks, Arc::clone(&bot))
+            .expect("Should create sinks");
+
         // 创建消息处理器
         let message_processor = Arc::new(MessageProcessor::new(
             config,
             ai_service.into(),
+            bot,
+            sinks,
         ));
 
         // 添加测试频道到监控列表